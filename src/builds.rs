@@ -0,0 +1,257 @@
+//! Listing the stable releases and dev runs available to install, without downloading them.
+
+use anyhow::{Context, Result};
+use colored::*;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::catalog::{self, Repo};
+use crate::http::{self, Http};
+use crate::progress::Reporter;
+use crate::BUILD_TYPES;
+
+#[derive(Serialize)]
+pub struct Listing {
+    pub stable: Vec<Release>,
+    pub dev: Vec<Run>,
+}
+
+#[derive(Serialize)]
+pub struct Release {
+    pub tag: String,
+    pub published_at: String,
+    /// Build types with a package among the assets
+    pub build_types: Vec<String>,
+    pub assets: Vec<Asset>,
+}
+
+#[derive(Serialize)]
+pub struct Run {
+    pub run_number: u64,
+    pub run_id: u64,
+    pub head_sha: String,
+    /// First line of the commit message
+    pub message: String,
+    pub created_at: String,
+    pub artifacts: Vec<Asset>,
+}
+
+#[derive(Serialize)]
+pub struct Asset {
+    pub name: String,
+    pub size: u64,
+    /// When GitHub deletes a run artifact; releases keep their assets
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<String>,
+}
+
+/// Recent stable releases and successful dev runs, keeping the ones matching `query`.
+pub fn list(client: &dyn Http, query: Option<&str>, limit: usize) -> Result<Listing> {
+    let query = query.map(str::to_lowercase);
+    Ok(Listing {
+        stable: fetch_releases(client, limit)?
+            .into_iter()
+            .filter(|release| query.as_deref().is_none_or(|query| release.matches(query)))
+            .collect(),
+        dev: fetch_runs(client, limit)?
+            .into_iter()
+            .filter(|run| query.as_deref().is_none_or(|query| run.matches(query)))
+            .collect(),
+    })
+}
+
+/// The latest `limit` stable releases, newest first.
+pub fn fetch_releases(client: &dyn Http, limit: usize) -> Result<Vec<Release>> {
+    let releases_url = format!(
+        "{}/repos/{}/releases?per_page={}",
+        catalog::get().hosts.api,
+        catalog::get().repo(Repo::Rtx),
+        limit
+    );
+    let releases = http::get_json(client, &releases_url)?;
+    Ok(releases
+        .as_array()
+        .context("Unexpected response listing releases")?
+        .iter()
+        .map(|release| {
+            let assets: Vec<Asset> = release["assets"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .map(|asset| Asset {
+                    name: text(&asset["name"]),
+                    size: asset["size"].as_u64().unwrap_or(0),
+                    expires_at: None,
+                })
+                .collect();
+            // Same naming as fetch_latest_stable_release looks for
+            let build_types = BUILD_TYPES
+                .iter()
+                .filter(|build_type| {
+                    assets.iter().any(|asset| {
+                        asset.name.ends_with(&format!("-{}.zip", build_type))
+                            && !asset.name.contains("-symbols")
+                    })
+                })
+                .map(|build_type| build_type.to_string())
+                .collect();
+            Release {
+                tag: text(&release["tag_name"]),
+                published_at: text(&release["published_at"]),
+                build_types,
+                assets,
+            }
+        })
+        .collect())
+}
+
+/// The latest `limit` successful dev runs on main with their artifacts, newest first.
+pub fn fetch_runs(client: &dyn Http, limit: usize) -> Result<Vec<Run>> {
+    let runs_url = format!(
+        "{}/repos/{}/actions/runs?branch=main&status=success&per_page={}",
+        catalog::get().hosts.api,
+        catalog::get().repo(Repo::Dxvk),
+        limit
+    );
+    let runs = http::get_json(client, &runs_url)?;
+    runs["workflow_runs"]
+        .as_array()
+        .context("Unexpected response listing workflow runs")?
+        .iter()
+        .filter(|run| run["conclusion"] == "success")
+        .map(|run| {
+            let artifacts_url = run["artifacts_url"]
+                .as_str()
+                .context("No artifacts URL found for a run")?;
+            let artifacts = http::get_json(client, artifacts_url)?;
+            Ok(Run {
+                run_number: run["run_number"].as_u64().unwrap_or(0),
+                run_id: run["id"].as_u64().unwrap_or(0),
+                head_sha: text(&run["head_sha"]),
+                message: text(&run["head_commit"]["message"])
+                    .lines()
+                    .next()
+                    .unwrap_or_default()
+                    .to_string(),
+                created_at: text(&run["created_at"]),
+                artifacts: artifacts["artifacts"]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .filter(|artifact| artifact["expired"] != true)
+                    .map(|artifact| Asset {
+                        name: text(&artifact["name"]),
+                        size: artifact["size_in_bytes"].as_u64().unwrap_or(0),
+                        expires_at: artifact["expires_at"].as_str().map(str::to_string),
+                    })
+                    .collect(),
+            })
+        })
+        .collect()
+}
+
+impl Release {
+    fn matches(&self, query: &str) -> bool {
+        self.tag.to_lowercase().contains(query)
+            || self
+                .build_types
+                .iter()
+                .any(|build_type| build_type == query)
+            || self
+                .assets
+                .iter()
+                .any(|asset| asset.name.to_lowercase().contains(query))
+    }
+}
+
+impl Run {
+    fn matches(&self, query: &str) -> bool {
+        self.run_number.to_string() == query
+            || self.head_sha.starts_with(query)
+            || self.message.to_lowercase().contains(query)
+            || self
+                .artifacts
+                .iter()
+                .any(|artifact| artifact.name.to_lowercase().contains(query))
+    }
+}
+
+/// Shows a listing as a table of releases and runs, each followed by its packages.
+pub fn print_table(listing: &Listing, reporter: &mut dyn Reporter) {
+    report!(reporter, "\n{}", "Stable releases".green().bold());
+    if listing.stable.is_empty() {
+        report!(reporter, "  none found");
+    } else {
+        report!(reporter, "  {:<24} {:<10} BUILD TYPES", "TAG", "DATE");
+    }
+    for release in &listing.stable {
+        report!(
+            reporter,
+            "  {:<24} {:<10} {}",
+            release.tag,
+            date(&release.published_at),
+            release.build_types.join(", ")
+        );
+        print_assets(&release.assets, reporter);
+    }
+
+    report!(reporter, "\n{}", "Dev builds".green().bold());
+    if listing.dev.is_empty() {
+        report!(reporter, "  none found");
+    } else {
+        report!(
+            reporter,
+            "  {:<8} {:<8} {:<10} MESSAGE",
+            "RUN",
+            "SHA",
+            "DATE"
+        );
+    }
+    for run in &listing.dev {
+        report!(
+            reporter,
+            "  {:<8} {:<8} {:<10} {}",
+            run.run_number,
+            run.head_sha.get(..7).unwrap_or(&run.head_sha),
+            date(&run.created_at),
+            run.message
+        );
+        print_assets(&run.artifacts, reporter);
+    }
+}
+
+fn print_assets(assets: &[Asset], reporter: &mut dyn Reporter) {
+    let width = assets
+        .iter()
+        .map(|asset| asset.name.len())
+        .max()
+        .unwrap_or(0);
+    for asset in assets {
+        let expiry = asset
+            .expires_at
+            .as_deref()
+            .map(|expires_at| format!("  expires {}", date(expires_at)))
+            .unwrap_or_default();
+        report!(
+            reporter,
+            "      {:<width$} {:>10}{}",
+            asset.name,
+            format_size(asset.size),
+            expiry.dimmed(),
+            width = width
+        );
+    }
+}
+
+pub fn format_size(bytes: u64) -> String {
+    format!("{:.1} MB", bytes as f64 / (1024.0 * 1024.0))
+}
+
+/// The date part of a GitHub timestamp.
+fn date(timestamp: &str) -> &str {
+    timestamp.get(..10).unwrap_or(timestamp)
+}
+
+fn text(value: &Value) -> String {
+    value.as_str().unwrap_or_default().to_string()
+}
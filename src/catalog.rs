@@ -0,0 +1,342 @@
+//! Optional catalog of repos, artifact names, auxiliary files and layout rules, fetched at
+//! startup so upstream renames can be followed without a new downloader build. Without
+//! one, or when it can't be loaded, the compiled-in constants are used.
+
+use std::fs;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use anyhow::{Context, Result};
+use colored::*;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+use crate::error::Error;
+use crate::http::{self, Http};
+use crate::layout::Rules;
+use crate::progress::Reporter;
+use crate::Arch;
+
+static CATALOG: OnceLock<Catalog> = OnceLock::new();
+
+/// One of the upstream repos, named like its key in the catalog's `[repos]` table
+#[derive(Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum Repo {
+    #[serde(rename = "dxvk_remix")]
+    Dxvk,
+    #[serde(rename = "bridge_remix")]
+    Bridge,
+    #[serde(rename = "rtx_remix")]
+    Rtx,
+}
+
+/// Every section is optional and defaults to the built-in one, so a catalog only needs
+/// what it changes
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct Catalog {
+    pub repos: Repos,
+    pub artifacts: Artifacts,
+    pub additional_files: Vec<AdditionalFile>,
+    pub licenses: Vec<License>,
+    pub bridge_files: Vec<BridgeFile>,
+    pub dxwrapper: Dxwrapper,
+    pub hosts: Hosts,
+    /// Replaces the built-in layout rules; a local remix-layout.toml still wins
+    pub layout: Option<Rules>,
+    /// Wrapper DLLs recognised by their hash when nothing inside them names the wrapper
+    pub wrapper_hashes: Vec<WrapperHash>,
+}
+
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct Repos {
+    pub dxvk_remix: String,
+    pub bridge_remix: String,
+    pub rtx_remix: String,
+}
+
+/// Which workflow artifacts hold each package, besides containing the build type
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct Artifacts {
+    pub x86: ArtifactPattern,
+    pub x64: ArtifactPattern,
+    pub bridge: ArtifactPattern,
+}
+
+#[derive(Deserialize)]
+pub struct ArtifactPattern {
+    #[serde(default)]
+    pub contains: Vec<String>,
+    #[serde(default)]
+    pub excludes: Vec<String>,
+}
+
+#[derive(Deserialize)]
+pub struct AdditionalFile {
+    pub name: String,
+    pub repo: Repo,
+    pub path: String,
+    #[serde(default)]
+    pub destination: String,
+}
+
+#[derive(Deserialize)]
+pub struct License {
+    pub name: String,
+    pub repo: Repo,
+    pub path: String,
+    /// Only downloaded for this architecture
+    pub arch: Option<Arch>,
+}
+
+#[derive(Deserialize)]
+pub struct BridgeFile {
+    pub name: String,
+    #[serde(default)]
+    pub destination: String,
+}
+
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct Dxwrapper {
+    pub binaries_url: String,
+    pub license_url: String,
+}
+
+#[derive(Deserialize)]
+pub struct WrapperHash {
+    /// Lowercase hex SHA-256 of the DLL
+    pub sha256: String,
+    /// The wrapper the DLL belongs to, such as ReShade
+    pub wrapper: String,
+}
+
+/// Base URLs of the services builds are fetched from, without a trailing slash
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct Hosts {
+    /// The GitHub REST API
+    pub api: String,
+    /// Raw files from GitHub repos
+    pub raw: String,
+    /// nightly.link, which serves workflow artifacts without signing in
+    pub nightly_link: String,
+}
+
+impl Default for Catalog {
+    fn default() -> Self {
+        Self::builtin()
+    }
+}
+
+impl Default for Repos {
+    fn default() -> Self {
+        Self {
+            dxvk_remix: crate::DXVK_REMIX_REPO.to_string(),
+            bridge_remix: crate::BRIDGE_REMIX_REPO.to_string(),
+            rtx_remix: crate::RTX_REMIX_REPO.to_string(),
+        }
+    }
+}
+
+impl Default for Artifacts {
+    fn default() -> Self {
+        Self {
+            x86: ArtifactPattern {
+                contains: vec![crate::X86_ARTIFACT.to_string()],
+                excludes: vec!["symbols".to_string()],
+            },
+            x64: ArtifactPattern {
+                contains: Vec::new(),
+                excludes: vec!["x86".to_string(), "symbols".to_string()],
+            },
+            bridge: ArtifactPattern {
+                contains: Vec::new(),
+                excludes: vec!["symbols".to_string()],
+            },
+        }
+    }
+}
+
+impl Default for Dxwrapper {
+    fn default() -> Self {
+        Self {
+            binaries_url: crate::DXWRAPPER_BINARIES_URL.to_string(),
+            license_url: crate::DXWRAPPER_LICENSE_URL.to_string(),
+        }
+    }
+}
+
+impl Default for Hosts {
+    fn default() -> Self {
+        Self {
+            api: crate::GITHUB_API_URL.to_string(),
+            raw: crate::GITHUB_RAW_URL.to_string(),
+            nightly_link: crate::NIGHTLY_LINK_URL.to_string(),
+        }
+    }
+}
+
+impl Catalog {
+    /// The catalog made from the constants compiled into the downloader.
+    pub fn builtin() -> Self {
+        Self {
+            repos: Repos::default(),
+            artifacts: Artifacts::default(),
+            additional_files: crate::ADDITIONAL_FILES
+                .iter()
+                .map(|(name, repo, path, destination)| AdditionalFile {
+                    name: name.to_string(),
+                    repo: *repo,
+                    path: path.to_string(),
+                    destination: destination.to_string(),
+                })
+                .collect(),
+            licenses: crate::LICENSES
+                .iter()
+                .map(|(name, repo, path, arch)| License {
+                    name: name.to_string(),
+                    repo: *repo,
+                    path: path.to_string(),
+                    arch: *arch,
+                })
+                .collect(),
+            bridge_files: crate::BRIDGE_FILES
+                .iter()
+                .map(|(name, destination)| BridgeFile {
+                    name: name.to_string(),
+                    destination: destination.to_string(),
+                })
+                .collect(),
+            dxwrapper: Dxwrapper::default(),
+            hosts: Hosts::default(),
+            layout: None,
+            wrapper_hashes: crate::WRAPPER_HASHES
+                .iter()
+                .map(|(sha256, wrapper)| WrapperHash {
+                    sha256: sha256.to_string(),
+                    wrapper: wrapper.to_string(),
+                })
+                .collect(),
+        }
+    }
+
+    /// `url` fetched from the catalog's hosts: a URL on one of the built-in hosts, such as
+    /// the default dxwrapper ones, moves to the host that replaces it.
+    pub fn on_hosts(&self, url: &str) -> String {
+        let hosts = [
+            (crate::GITHUB_API_URL, &self.hosts.api),
+            (crate::GITHUB_RAW_URL, &self.hosts.raw),
+            (crate::NIGHTLY_LINK_URL, &self.hosts.nightly_link),
+        ];
+        for (builtin, host) in hosts {
+            if let Some(rest) = url.strip_prefix(builtin) {
+                if rest.starts_with('/') {
+                    return format!("{}{}", host, rest);
+                }
+            }
+        }
+        url.to_string()
+    }
+
+    pub fn repo(&self, repo: Repo) -> &str {
+        match repo {
+            Repo::Dxvk => &self.repos.dxvk_remix,
+            Repo::Bridge => &self.repos.bridge_remix,
+            Repo::Rtx => &self.repos.rtx_remix,
+        }
+    }
+}
+
+impl ArtifactPattern {
+    pub fn matches(&self, name: &str, build_type: &str) -> bool {
+        name.contains(build_type)
+            && self
+                .contains
+                .iter()
+                .all(|part| name.contains(part.as_str()))
+            && !self
+                .excludes
+                .iter()
+                .any(|part| name.contains(part.as_str()))
+    }
+}
+
+/// The catalog in use, the built-in one unless `init` loaded another.
+pub fn get() -> &'static Catalog {
+    CATALOG.get_or_init(Catalog::builtin)
+}
+
+/// Uses `catalog` instead of the built-in one, for programs using the library. Like
+/// `init`, it only has an effect before anything reads the catalog.
+pub fn set(catalog: Catalog) {
+    let _ = CATALOG.set(catalog);
+}
+
+/// Loads the catalog at a URL or path, falling back to the built-in one with a warning.
+/// Remote catalogs must be pinned with their SHA-256; local files are only checked when
+/// a hash is given.
+pub fn init(client: &dyn Http, location: &str, sha256: Option<&str>, reporter: &mut dyn Reporter) {
+    let catalog = match load(client, location, sha256, reporter) {
+        Ok(catalog) => {
+            report!(
+                reporter,
+                "{}",
+                format!("Using the package catalog from {}", location).cyan()
+            );
+            catalog
+        }
+        Err(e) => {
+            reporter.warning(&format!(
+                "Could not use the package catalog ({:#}), using the built-in one",
+                e
+            ));
+            Catalog::builtin()
+        }
+    };
+    // Only main initializes the catalog, before anything reads it
+    set(catalog);
+}
+
+fn load(
+    client: &dyn Http,
+    location: &str,
+    sha256: Option<&str>,
+    reporter: &mut dyn Reporter,
+) -> Result<Catalog> {
+    let is_remote = location.starts_with("http://") || location.starts_with("https://");
+    let bytes = if is_remote {
+        if sha256.is_none() {
+            anyhow::bail!("remote catalogs need --catalog-sha256");
+        }
+        http::get_bytes(client, location)?
+    } else {
+        fs::read(location).map_err(|e| Error::filesystem(Path::new(location), e))?
+    };
+
+    if let Some(expected) = sha256 {
+        let actual = format!("{:x}", Sha256::digest(&bytes));
+        if !actual.eq_ignore_ascii_case(expected) {
+            anyhow::bail!("its SHA-256 is {}, expected {}", actual, expected);
+        }
+    }
+
+    let text = String::from_utf8(bytes).context("it is not UTF-8")?;
+    // Keys from newer catalogs are skipped, so older downloaders can still use them
+    let mut ignored = Vec::new();
+    let catalog: Catalog = serde_ignored::deserialize(toml::Deserializer::new(&text), |path| {
+        ignored.push(path.to_string())
+    })?;
+    if !ignored.is_empty() {
+        reporter.warning(&format!(
+            "Ignoring catalog settings this version doesn't know: {}",
+            ignored.join(", ")
+        ));
+    }
+    if let Some(rules) = &catalog.layout {
+        rules.validate()?;
+    }
+    Ok(catalog)
+}
@@ -0,0 +1,965 @@
+//! Reading, editing and merging Remix's `key = value` config files (`dxvk.conf`,
+//! `rtx.conf`, `bridge.conf`).
+//!
+//! Each install keeps a pristine copy of the upstream configs in `.remix-defaults`,
+//! so an update can three-way merge: upstream changes are applied, values the
+//! user edited are kept, and keys changed on both sides are reported.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::Path;
+
+use anyhow::Result;
+use colored::*;
+use serde::Serialize;
+
+use crate::catalog;
+use crate::download::raw_url;
+use crate::error::Error;
+use crate::http::{self, Http};
+use crate::progress::Reporter;
+
+/// Config files that are merged on update rather than overwritten
+pub const MERGED_CONFIGS: [&str; 3] = ["dxvk.conf", "rtx.conf", ".trex/bridge.conf"];
+pub const DEFAULTS_DIR: &str = ".remix-defaults";
+
+/// A setting's identity: the `[section]` it sits under ("" for none) and its key
+pub type SettingKey = (String, String);
+
+pub struct ConfigFile {
+    pub lines: Vec<Line>,
+}
+
+pub struct Line {
+    pub raw: String,
+    pub kind: LineKind,
+}
+
+pub enum LineKind {
+    Setting {
+        key: String,
+        value: String,
+    },
+    /// A commented-out `# key = value`, how upstream documents optional settings
+    Disabled {
+        key: String,
+        value: String,
+    },
+    Section(String),
+    Text,
+}
+
+impl ConfigFile {
+    pub fn parse(text: &str) -> Self {
+        let lines = text
+            .lines()
+            .map(|raw| Line {
+                raw: raw.to_string(),
+                kind: parse_line(raw),
+            })
+            .collect();
+        Self { lines }
+    }
+
+    /// Active settings keyed by section and name. Later duplicates win, as they do at runtime.
+    pub fn settings(&self) -> BTreeMap<SettingKey, String> {
+        let mut settings = BTreeMap::new();
+        let mut section = String::new();
+        for line in &self.lines {
+            match &line.kind {
+                LineKind::Section(name) => section = name.clone(),
+                LineKind::Setting { key, value } => {
+                    settings.insert((section.clone(), key.clone()), value.clone());
+                }
+                _ => {}
+            }
+        }
+        settings
+    }
+
+    /// Every option the file mentions, active or commented out, with the value it
+    /// documents. Upstream's `dxvk.conf` lists all runtime options this way.
+    pub fn documented_options(&self) -> BTreeMap<String, String> {
+        let mut options = BTreeMap::new();
+        for line in &self.lines {
+            if let LineKind::Setting { key, value } | LineKind::Disabled { key, value } = &line.kind
+            {
+                options.entry(key.clone()).or_insert_with(|| value.clone());
+            }
+        }
+        options
+    }
+
+    pub fn get(&self, section: &str, key: &str) -> Option<&str> {
+        self.active_lines(section, key)
+            .last()
+            .and_then(|&index| match &self.lines[index].kind {
+                LineKind::Setting { value, .. } => Some(value.as_str()),
+                _ => None,
+            })
+    }
+
+    /// Sets a value in place, under the option's commented-out documentation if it
+    /// has some, or at the end of its section otherwise.
+    pub fn set(&mut self, section: &str, key: &str, value: &str) {
+        let line = Line {
+            raw: format!("{} = {}", key, value),
+            kind: LineKind::Setting {
+                key: key.to_string(),
+                value: value.to_string(),
+            },
+        };
+
+        let active = self.active_lines(section, key);
+        if let Some((&first, duplicates)) = active.split_first() {
+            for &index in duplicates.iter().rev() {
+                self.lines.remove(index);
+            }
+            self.lines[first] = line;
+            return;
+        }
+
+        let sections = self.line_sections();
+        let documented = self.lines.iter().enumerate().position(|(index, line)| {
+            sections[index] == section
+                && matches!(&line.kind, LineKind::Disabled { key: k, .. } if k == key)
+        });
+        let insert_at = match documented {
+            Some(index) => index + 1,
+            None => match sections.iter().rposition(|s| s == section) {
+                // Right after the section's last setting, ahead of any trailing blank lines
+                Some(last) => {
+                    let mut at = last + 1;
+                    while at > 0
+                        && matches!(self.lines[at - 1].kind, LineKind::Text)
+                        && self.lines[at - 1].raw.trim().is_empty()
+                    {
+                        at -= 1;
+                    }
+                    at
+                }
+                None if section.is_empty() => 0,
+                None => {
+                    if self
+                        .lines
+                        .last()
+                        .is_some_and(|line| !line.raw.trim().is_empty())
+                    {
+                        self.lines.push(Line {
+                            raw: String::new(),
+                            kind: LineKind::Text,
+                        });
+                    }
+                    self.lines.push(Line {
+                        raw: format!("[{}]", section),
+                        kind: LineKind::Section(section.to_string()),
+                    });
+                    self.lines.len()
+                }
+            },
+        };
+        self.lines.insert(insert_at, line);
+    }
+
+    /// Removes every active line for a key, returning whether there was one.
+    pub fn unset(&mut self, section: &str, key: &str) -> bool {
+        let active = self.active_lines(section, key);
+        for &index in active.iter().rev() {
+            self.lines.remove(index);
+        }
+        !active.is_empty()
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text: String = self
+            .lines
+            .iter()
+            .map(|line| line.raw.as_str())
+            .collect::<Vec<_>>()
+            .join("\n");
+        text.push('\n');
+        text
+    }
+
+    fn active_lines(&self, section: &str, key: &str) -> Vec<usize> {
+        let sections = self.line_sections();
+        self.lines
+            .iter()
+            .enumerate()
+            .filter(|(index, line)| {
+                sections[*index] == section
+                    && matches!(&line.kind, LineKind::Setting { key: k, .. } if k == key)
+            })
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    /// The section each line belongs to, with section headers counting as part of their own section.
+    fn line_sections(&self) -> Vec<String> {
+        let mut section = String::new();
+        self.lines
+            .iter()
+            .map(|line| {
+                if let LineKind::Section(name) = &line.kind {
+                    section = name.clone();
+                }
+                section.clone()
+            })
+            .collect()
+    }
+}
+
+fn parse_line(raw: &str) -> LineKind {
+    let trimmed = raw.trim();
+    if let Some(name) = trimmed.strip_prefix('[').and_then(|s| s.strip_suffix(']')) {
+        return LineKind::Section(name.trim().to_string());
+    }
+    if let Some(commented) = trimmed.strip_prefix('#') {
+        return match split_setting(commented.trim()) {
+            Some((key, value)) => LineKind::Disabled { key, value },
+            None => LineKind::Text,
+        };
+    }
+    match split_setting(trimmed) {
+        Some((key, value)) => LineKind::Setting { key, value },
+        None => LineKind::Text,
+    }
+}
+
+fn split_setting(text: &str) -> Option<(String, String)> {
+    let (key, value) = text.split_once('=')?;
+    let key = key.trim();
+    let is_key = !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'));
+    is_key.then(|| (key.to_string(), value.trim().to_string()))
+}
+
+pub struct MergeConflict {
+    pub key: SettingKey,
+    pub base: Option<String>,
+    pub user: Option<String>,
+    pub upstream: Option<String>,
+}
+
+pub struct MergeResult {
+    pub text: String,
+    /// Settings where the user's value was kept over the upstream default
+    pub kept: usize,
+    /// Upstream changes applied to settings the user had not touched
+    pub updated: usize,
+    pub conflicts: Vec<MergeConflict>,
+}
+
+/// Three-way merges a user's config with a new upstream one, using the upstream
+/// file the user's copy started from as the base. Without a base every value the
+/// user has set is treated as their own edit.
+pub fn merge(base: Option<&ConfigFile>, user: &ConfigFile, upstream: &ConfigFile) -> MergeResult {
+    let base_settings = base.map(ConfigFile::settings);
+    let user_settings = user.settings();
+    let upstream_settings = upstream.settings();
+
+    let keys: BTreeSet<&SettingKey> = user_settings
+        .keys()
+        .chain(upstream_settings.keys())
+        .chain(base_settings.iter().flat_map(|settings| settings.keys()))
+        .collect();
+
+    let mut resolved: BTreeMap<SettingKey, Option<String>> = BTreeMap::new();
+    let mut kept = 0;
+    let mut updated = 0;
+    let mut conflicts = Vec::new();
+    for key in keys {
+        let user_value = user_settings.get(key);
+        let upstream_value = upstream_settings.get(key);
+        let value = match &base_settings {
+            None => user_value.or(upstream_value),
+            Some(base_settings) => {
+                let base_value = base_settings.get(key);
+                if user_value == upstream_value || user_value == base_value {
+                    if user_value != upstream_value {
+                        updated += 1;
+                    }
+                    upstream_value
+                } else {
+                    if upstream_value != base_value {
+                        conflicts.push(MergeConflict {
+                            key: key.clone(),
+                            base: base_value.cloned(),
+                            user: user_value.cloned(),
+                            upstream: upstream_value.cloned(),
+                        });
+                    }
+                    user_value
+                }
+            }
+        };
+        if value.is_some() && value == user_value && value != upstream_value {
+            kept += 1;
+        }
+        resolved.insert(key.clone(), value.cloned());
+    }
+
+    MergeResult {
+        text: render(upstream, &resolved),
+        kept,
+        updated,
+        conflicts,
+    }
+}
+
+/// Lays resolved settings out along the upstream file, so new comments and
+/// options show up where upstream put them.
+fn render(upstream: &ConfigFile, resolved: &BTreeMap<SettingKey, Option<String>>) -> String {
+    let upstream_settings = upstream.settings();
+    // A key set twice takes the later value at runtime, so that's where ours goes
+    let mut last_active: BTreeMap<SettingKey, usize> = BTreeMap::new();
+    let mut section = String::new();
+    for (index, line) in upstream.lines.iter().enumerate() {
+        match &line.kind {
+            LineKind::Section(name) => section = name.clone(),
+            LineKind::Setting { key, .. } => {
+                last_active.insert((section.clone(), key.clone()), index);
+            }
+            _ => {}
+        }
+    }
+
+    let mut written: BTreeSet<SettingKey> = BTreeSet::new();
+    let mut out: Vec<String> = Vec::new();
+    let mut first_section_at = None;
+    // Where each section's header or last setting ends in `out`
+    let mut section_ends: BTreeMap<String, usize> = BTreeMap::new();
+    let mut section = String::new();
+
+    for (index, line) in upstream.lines.iter().enumerate() {
+        match &line.kind {
+            LineKind::Section(name) => {
+                first_section_at.get_or_insert(out.len());
+                section = name.clone();
+                out.push(line.raw.clone());
+            }
+            LineKind::Setting { key, value } => {
+                let id = (section.clone(), key.clone());
+                match resolved.get(&id) {
+                    Some(Some(resolved_value)) if last_active.get(&id) == Some(&index) => {
+                        written.insert(id.clone());
+                        if resolved_value == value {
+                            out.push(line.raw.clone());
+                        } else {
+                            out.push(format!("{} = {}", key, resolved_value));
+                        }
+                    }
+                    Some(None) => out.push(format!("# {}", line.raw.trim_start())),
+                    _ => out.push(line.raw.clone()),
+                }
+            }
+            LineKind::Disabled { key, .. } => {
+                let id = (section.clone(), key.clone());
+                out.push(line.raw.clone());
+                // Enable documented options right under their upstream description
+                if let Some(Some(resolved_value)) = resolved.get(&id) {
+                    if !upstream_settings.contains_key(&id) && written.insert(id.clone()) {
+                        out.push(format!("{} = {}", key, resolved_value));
+                    }
+                }
+            }
+            LineKind::Text => out.push(line.raw.clone()),
+        }
+        // Comments after a section's last setting often introduce the next one
+        if !section.is_empty() && !matches!(line.kind, LineKind::Text) {
+            section_ends.insert(section.clone(), out.len());
+        }
+    }
+
+    // Settings upstream doesn't mention at all
+    let mut leftover: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+    for ((section, key), value) in resolved {
+        if let Some(value) = value {
+            if !written.contains(&(section.clone(), key.clone())) {
+                leftover
+                    .entry(section.as_str())
+                    .or_default()
+                    .push(format!("{} = {}", key, value));
+            }
+        }
+    }
+    let global = leftover.remove("");
+
+    // Sections upstream has get the settings at their end, later sections first so
+    // the earlier positions stay valid
+    let mut existing: Vec<(usize, Vec<String>)> = Vec::new();
+    leftover.retain(|section, settings| match section_ends.get(*section) {
+        Some(&end) => {
+            existing.push((end, std::mem::take(settings)));
+            false
+        }
+        None => true,
+    });
+    existing.sort_by_key(|(end, _)| std::cmp::Reverse(*end));
+    for (end, settings) in existing {
+        out.splice(end..end, settings);
+    }
+
+    if let Some(global) = global {
+        let at = first_section_at.unwrap_or(out.len());
+        let mut block = vec!["# Kept from your previous config".to_string()];
+        block.extend(global);
+        block.push(String::new());
+        out.splice(at..at, block);
+    }
+    for (section, settings) in leftover {
+        out.push(String::new());
+        out.push(format!("[{}]", section));
+        out.extend(settings);
+    }
+
+    let mut text = out.join("\n");
+    text.push('\n');
+    text
+}
+
+/// A config file as the user left it, and the upstream copy it started from.
+pub struct SavedConfig {
+    pub path: &'static str,
+    pub user: String,
+    pub base: Option<String>,
+}
+
+/// Reads the current configs and their recorded defaults from an install, before it is replaced.
+pub fn capture_configs(install_path: &Path) -> Vec<SavedConfig> {
+    MERGED_CONFIGS
+        .iter()
+        .filter_map(|path| {
+            let user = fs::read_to_string(install_path.join(path)).ok()?;
+            let base = fs::read_to_string(install_path.join(DEFAULTS_DIR).join(path)).ok();
+            Some(SavedConfig { path, user, base })
+        })
+        .collect()
+}
+
+/// Keeps a pristine copy of the upstream configs in a freshly assembled build.
+pub fn store_defaults(final_path: &Path) -> Result<()> {
+    for path in MERGED_CONFIGS {
+        let config_path = final_path.join(path);
+        if config_path.exists() {
+            let default_path = final_path.join(DEFAULTS_DIR).join(path);
+            if let Some(parent) = default_path.parent() {
+                fs::create_dir_all(parent).map_err(|e| Error::filesystem(parent, e))?;
+            }
+            fs::copy(&config_path, &default_path)
+                .map_err(|e| Error::filesystem(&default_path, e))?;
+        }
+    }
+    Ok(())
+}
+
+/// Merges saved user configs with the upstream defaults stored in `package_path`,
+/// returning the contents to write for each file. Configs upstream doesn't ship
+/// are passed through unchanged.
+pub fn merge_saved(
+    saved: &[SavedConfig],
+    package_path: &Path,
+    reporter: &mut dyn Reporter,
+) -> Vec<(&'static str, String)> {
+    let mut merged = Vec::new();
+    for config in saved {
+        let upstream_path = package_path.join(DEFAULTS_DIR).join(config.path);
+        let Ok(upstream_text) = fs::read_to_string(&upstream_path) else {
+            merged.push((config.path, config.user.clone()));
+            continue;
+        };
+        if config.base.as_deref() == Some(config.user.as_str()) {
+            // Never edited, so just take the new upstream file
+            merged.push((config.path, upstream_text));
+            continue;
+        }
+
+        let base = config.base.as_deref().map(ConfigFile::parse);
+        let result = merge(
+            base.as_ref(),
+            &ConfigFile::parse(&config.user),
+            &ConfigFile::parse(&upstream_text),
+        );
+        print_merge(config.path, &result, reporter);
+        merged.push((config.path, result.text));
+    }
+    merged
+}
+
+fn print_merge(path: &str, result: &MergeResult, reporter: &mut dyn Reporter) {
+    report!(
+        reporter,
+        "{}",
+        format!(
+            "Merged {}: kept {} of your settings, applied {} upstream changes",
+            path, result.kept, result.updated
+        )
+        .cyan()
+    );
+    for conflict in &result.conflicts {
+        let (section, key) = &conflict.key;
+        let name = if section.is_empty() {
+            key.clone()
+        } else {
+            format!("[{}] {}", section, key)
+        };
+        reporter.warning(&format!(
+            "Conflict in {}: {} was changed upstream from {} to {}, keeping your {}",
+            path,
+            name,
+            describe_value(&conflict.base),
+            describe_value(&conflict.upstream),
+            describe_value(&conflict.user)
+        ));
+    }
+}
+
+fn describe_value(value: &Option<String>) -> String {
+    match value {
+        Some(value) => format!("\"{}\"", value),
+        None => "unset".to_string(),
+    }
+}
+
+// === Option changes between builds ===
+
+/// How upstream documents an option: its value and the comment block above it.
+pub struct OptionDoc {
+    pub value: String,
+    pub comment: String,
+}
+
+/// Reads the documentation for every option, taking the comment lines directly
+/// above each one, across the blank line upstream leaves before the option.
+pub fn option_docs(config: &ConfigFile) -> BTreeMap<String, OptionDoc> {
+    let mut docs = BTreeMap::new();
+    let mut pending: Vec<&str> = Vec::new();
+    let mut after_option = false;
+    for line in &config.lines {
+        match &line.kind {
+            LineKind::Setting { key, value } | LineKind::Disabled { key, value } => {
+                docs.entry(key.clone()).or_insert_with(|| OptionDoc {
+                    value: value.clone(),
+                    comment: pending.join("\n"),
+                });
+                after_option = true;
+            }
+            LineKind::Text if line.raw.trim_start().starts_with('#') => {
+                if after_option {
+                    pending.clear();
+                    after_option = false;
+                }
+                pending.push(line.raw.trim_start().trim_start_matches('#').trim());
+            }
+            LineKind::Text if line.raw.trim().is_empty() => {}
+            _ => {
+                pending.clear();
+                after_option = false;
+            }
+        }
+    }
+    docs
+}
+
+#[derive(Default)]
+pub struct OptionDiff {
+    pub added: Vec<(String, String)>,
+    pub removed: Vec<(String, String)>,
+    /// Options whose documented value changed: key, old value, new value
+    pub changed: Vec<(String, String, String)>,
+    /// Options whose description changed: key and the new description
+    pub recommented: Vec<(String, String)>,
+}
+
+impl OptionDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.changed.is_empty()
+            && self.recommented.is_empty()
+    }
+}
+
+pub fn diff_options(old: &ConfigFile, new: &ConfigFile) -> OptionDiff {
+    let old_docs = option_docs(old);
+    let new_docs = option_docs(new);
+    let mut diff = OptionDiff::default();
+
+    for (key, new_doc) in &new_docs {
+        match old_docs.get(key) {
+            None => diff.added.push((key.clone(), new_doc.value.clone())),
+            Some(old_doc) => {
+                if old_doc.value != new_doc.value {
+                    diff.changed
+                        .push((key.clone(), old_doc.value.clone(), new_doc.value.clone()));
+                }
+                if old_doc.comment != new_doc.comment {
+                    diff.recommented
+                        .push((key.clone(), new_doc.comment.clone()));
+                }
+            }
+        }
+    }
+    for (key, old_doc) in &old_docs {
+        if !new_docs.contains_key(key) {
+            diff.removed.push((key.clone(), old_doc.value.clone()));
+        }
+    }
+    diff
+}
+
+pub fn print_option_diff(path: &str, diff: &OptionDiff, reporter: &mut dyn Reporter) {
+    if diff.is_empty() {
+        report!(
+            reporter,
+            "{}",
+            format!("No option changes in {}", path).green()
+        );
+        return;
+    }
+    report!(
+        reporter,
+        "{}",
+        format!(
+            "Option changes in {}: {} added, {} removed, {} new defaults, {} new descriptions",
+            path,
+            diff.added.len(),
+            diff.removed.len(),
+            diff.changed.len(),
+            diff.recommented.len()
+        )
+        .cyan()
+    );
+    for (key, value) in &diff.added {
+        report!(reporter, "  {} {} = {}", "+".green(), key, value);
+    }
+    for (key, value) in &diff.removed {
+        report!(reporter, "  {} {} = {}", "-".red(), key, value);
+    }
+    for (key, old, new) in &diff.changed {
+        report!(reporter, "  {} {}: {} -> {}", "~".yellow(), key, old, new);
+    }
+    for (key, comment) in &diff.recommented {
+        report!(reporter, "  {} {} description:", "~".yellow(), key);
+        for line in comment.lines() {
+            report!(reporter, "      {}", line.dimmed());
+        }
+    }
+}
+
+/// Reads the upstream default of a config file stored with an install.
+pub fn read_default(install_path: &Path, path: &str) -> Option<ConfigFile> {
+    let text = fs::read_to_string(install_path.join(DEFAULTS_DIR).join(path)).ok()?;
+    Some(ConfigFile::parse(&text))
+}
+
+/// Compares the options documented in the dxvk.conf an install came with to a newer one,
+/// by default the latest upstream.
+pub fn diff_installed(
+    client: &dyn Http,
+    install_path: &Path,
+    against: Option<&Path>,
+    reporter: &mut dyn Reporter,
+) -> Result<OptionDiff> {
+    let installed = read_default(install_path, "dxvk.conf")
+        .or_else(|| {
+            let text = fs::read_to_string(install_path.join("dxvk.conf")).ok()?;
+            Some(ConfigFile::parse(&text))
+        })
+        .ok_or_else(|| Error::not_found(format!("a dxvk.conf in {}", install_path.display())))?;
+
+    let new_text = match against {
+        Some(path) => fs::read_to_string(path).map_err(|e| Error::filesystem(path, e))?,
+        None => {
+            let file = catalog::get()
+                .additional_files
+                .iter()
+                .find(|file| file.name == "dxvk.conf")
+                .ok_or_else(|| Error::not_found("an upstream dxvk.conf in the catalog"))?;
+            report!(
+                reporter,
+                "{}",
+                "Fetching the latest upstream dxvk.conf...".cyan()
+            );
+            http::get_text(client, &raw_url(file.repo, "main", &file.path))?
+        }
+    };
+    Ok(diff_options(&installed, &ConfigFile::parse(&new_text)))
+}
+
+// === config get/set/unset/check ===
+
+/// Maps the file names users know to where they live in an install.
+pub fn config_path(file: &str) -> &'static str {
+    match file {
+        "bridge.conf" => ".trex/bridge.conf",
+        "rtx.conf" => "rtx.conf",
+        _ => "dxvk.conf",
+    }
+}
+
+fn load_config(install_path: &Path, path: &str) -> Result<ConfigFile> {
+    let config_path = install_path.join(path);
+    if !config_path.exists() {
+        return Ok(ConfigFile::parse(""));
+    }
+    let text = fs::read_to_string(&config_path).map_err(|e| Error::filesystem(&config_path, e))?;
+    Ok(ConfigFile::parse(&text))
+}
+
+/// Options upstream documents for a config file. `rtx.conf` has no upstream copy
+/// of its own; the options it takes are the ones documented in `dxvk.conf`.
+fn known_options(install_path: &Path, path: &str) -> Option<BTreeMap<String, String>> {
+    let reference = if path == "rtx.conf" {
+        "dxvk.conf"
+    } else {
+        path
+    };
+    [
+        install_path.join(DEFAULTS_DIR).join(reference),
+        install_path.join(reference),
+    ]
+    .iter()
+    .find_map(|candidate| fs::read_to_string(candidate).ok())
+    .map(|text| ConfigFile::parse(&text).documented_options())
+}
+
+/// Closest documented option to a mistyped key, if any is close enough to be a typo.
+fn suggest<'a>(key: &str, known: &'a BTreeMap<String, String>) -> Option<&'a str> {
+    known
+        .keys()
+        .map(|candidate| (edit_distance(key, candidate), candidate))
+        .filter(|(distance, _)| *distance <= (key.len() / 5).max(2))
+        .min()
+        .map(|(_, candidate)| candidate.as_str())
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+fn unknown_key_message(key: &str, path: &str, known: &BTreeMap<String, String>) -> String {
+    match suggest(key, known) {
+        Some(suggestion) => format!(
+            "{} is not a known {} option, did you mean {}?",
+            key, path, suggestion
+        ),
+        None => format!("{} is not a known {} option", key, path),
+    }
+}
+
+/// A setting read by `get`
+#[derive(Serialize)]
+pub struct Setting {
+    pub key: String,
+    /// Where the file lives in the install, such as `.trex/bridge.conf`
+    pub path: &'static str,
+    /// The value set in the file, if any
+    pub value: Option<String>,
+    /// The value upstream documents for it, if any
+    pub default: Option<String>,
+}
+
+/// Reads a setting, warning when upstream doesn't document the key.
+pub fn get(
+    install_path: &Path,
+    file: &str,
+    section: &str,
+    key: &str,
+    reporter: &mut dyn Reporter,
+) -> Result<Setting> {
+    let path = config_path(file);
+    let config = load_config(install_path, path)?;
+    let known = known_options(install_path, path);
+    let setting = Setting {
+        key: key.to_string(),
+        path,
+        value: config.get(section, key).map(str::to_string),
+        default: known.as_ref().and_then(|known| known.get(key).cloned()),
+    };
+    if let Some(known) = known.filter(|known| !known.contains_key(key)) {
+        reporter.warning(&unknown_key_message(key, path, &known));
+    }
+    Ok(setting)
+}
+
+pub fn set(
+    install_path: &Path,
+    file: &str,
+    section: &str,
+    key: &str,
+    value: &str,
+    force: bool,
+    reporter: &mut dyn Reporter,
+) -> Result<()> {
+    let path = config_path(file);
+    match known_options(install_path, path) {
+        Some(known) if !known.contains_key(key) => {
+            let message = unknown_key_message(key, path, &known);
+            if !force {
+                anyhow::bail!(Error::invalid(format!(
+                    "{} (pass --force to set it anyway)",
+                    message
+                )));
+            }
+            reporter.warning(&message);
+        }
+        Some(_) => {}
+        None => reporter.warning(&format!(
+            "No upstream {} found to check option names against",
+            path
+        )),
+    }
+
+    let mut config = load_config(install_path, path)?;
+    config.set(section, key, value);
+    let config_path = install_path.join(path);
+    fs::write(&config_path, config.to_text()).map_err(|e| Error::filesystem(&config_path, e))?;
+    report!(
+        reporter,
+        "{}",
+        format!("Set {} = {} in {}", key, value, path).green()
+    );
+    Ok(())
+}
+
+pub fn unset(
+    install_path: &Path,
+    file: &str,
+    section: &str,
+    key: &str,
+    reporter: &mut dyn Reporter,
+) -> Result<()> {
+    let path = config_path(file);
+    let mut config = load_config(install_path, path)?;
+    if !config.unset(section, key) {
+        report!(reporter, "{} was not set in {}", key, path);
+        return Ok(());
+    }
+    let config_path = install_path.join(path);
+    fs::write(&config_path, config.to_text()).map_err(|e| Error::filesystem(&config_path, e))?;
+    report!(
+        reporter,
+        "{}",
+        format!("Removed {} from {}", key, path).green()
+    );
+    Ok(())
+}
+
+/// Flags every active setting that upstream doesn't document, across all config files.
+pub fn check(install_path: &Path, reporter: &mut dyn Reporter) -> Result<()> {
+    let mut unknown = 0;
+    for path in MERGED_CONFIGS {
+        if !install_path.join(path).exists() {
+            continue;
+        }
+        let Some(known) = known_options(install_path, path) else {
+            continue;
+        };
+        let config = load_config(install_path, path)?;
+        for (line_number, line) in config.lines.iter().enumerate() {
+            if let LineKind::Setting { key, .. } = &line.kind {
+                if !known.contains_key(key) {
+                    unknown += 1;
+                    report!(
+                        reporter,
+                        "{}:{}: {}",
+                        path,
+                        line_number + 1,
+                        unknown_key_message(key, path, &known).yellow()
+                    );
+                }
+            }
+        }
+    }
+
+    if unknown > 0 {
+        anyhow::bail!(Error::invalid(format!("Found {} unknown options", unknown)));
+    }
+    report!(reporter, "{}", "All options are known".green());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn merge_text(base: &str, user: &str, upstream: &str) -> MergeResult {
+        merge(
+            Some(&ConfigFile::parse(base)),
+            &ConfigFile::parse(user),
+            &ConfigFile::parse(upstream),
+        )
+    }
+
+    #[test]
+    fn keeps_user_edits_next_to_upstream_changes() {
+        let result = merge_text("a = 1\nb = 2\n", "a = 10\nb = 2\n", "a = 1\nb = 3\n");
+        assert_eq!(result.text, "a = 10\nb = 3\n");
+        assert_eq!((result.kept, result.updated), (1, 1));
+        assert!(result.conflicts.is_empty());
+    }
+
+    #[test]
+    fn keeps_the_user_value_when_both_sides_change_a_key() {
+        let result = merge_text("a = 1\n", "a = 2\n", "a = 3\n");
+        assert_eq!(result.text, "a = 2\n");
+        let conflict = &result.conflicts[0];
+        assert_eq!(conflict.key, (String::new(), "a".to_string()));
+        assert_eq!(conflict.base.as_deref(), Some("1"));
+        assert_eq!(conflict.user.as_deref(), Some("2"));
+        assert_eq!(conflict.upstream.as_deref(), Some("3"));
+    }
+
+    #[test]
+    fn comments_out_settings_the_user_deleted() {
+        let result = merge_text("a = 1\nb = 2\n", "b = 2\n", "a = 1\nb = 2\n");
+        assert_eq!(result.text, "# a = 1\nb = 2\n");
+        assert!(result.conflicts.is_empty());
+    }
+
+    #[test]
+    fn keeps_documented_options_the_user_enabled_under_their_description() {
+        let base = "# Frame limit\n# a = 0\n";
+        let user = "# Frame limit\n# a = 0\na = 60\n";
+        let upstream = "# Frame limit\n# a = 0\nb = 2\n";
+        let result = merge_text(base, user, upstream);
+        assert_eq!(result.text, "# Frame limit\n# a = 0\na = 60\nb = 2\n");
+    }
+
+    #[test]
+    fn writes_a_duplicated_key_where_its_last_copy_is() {
+        let upstream = "a = 1\nb = 2\na = 1\n";
+        let result = merge_text(upstream, "a = 1\nb = 2\na = 5\n", upstream);
+        assert_eq!(result.text, "a = 1\nb = 2\na = 5\n");
+    }
+
+    #[test]
+    fn adds_new_keys_with_their_section_settings() {
+        let upstream = "[a]\nx = 1\n\n# Options for b\n[b]\ny = 2\n";
+        let user = "[a]\nx = 1\nz = 3\n\n# Options for b\n[b]\ny = 2\n";
+        let result = merge_text(upstream, user, upstream);
+        assert_eq!(result.text, user);
+    }
+
+    #[test]
+    fn keeps_global_settings_upstream_dropped_ahead_of_the_sections() {
+        let result = merge_text("a = 1\n[s]\nx = 1\n", "a = 2\n[s]\nx = 1\n", "[s]\nx = 1\n");
+        assert_eq!(
+            result.text,
+            "# Kept from your previous config\na = 2\n\n[s]\nx = 1\n"
+        );
+    }
+}
@@ -0,0 +1,177 @@
+//! Detecting third-party Direct3D wrappers in a game folder that stop Remix from loading.
+
+use std::fs;
+use std::path::Path;
+
+use anyhow::Result;
+use colored::*;
+use serde::Serialize;
+
+use crate::catalog;
+use crate::deploy::InstallRecord;
+use crate::error::Error;
+use crate::files::sha256_file;
+use crate::pe::PeFile;
+use crate::progress::Reporter;
+
+/// DLL names that games load from their own folder before the system copy,
+/// which is how wrappers inject themselves
+const PROXY_DLLS: [&str; 8] = [
+    "d3d8.dll",
+    "d3d9.dll",
+    "d3d10.dll",
+    "d3d11.dll",
+    "dxgi.dll",
+    "ddraw.dll",
+    "dinput8.dll",
+    "opengl32.dll",
+];
+
+/// Names Remix itself puts in the game folder and replaces anyway
+const REPLACED_BY_REMIX: [&str; 2] = ["d3d8.dll", "d3d9.dll"];
+
+/// Lowercase fragments of version resource strings or file contents, and the wrapper they identify
+const KNOWN_WRAPPERS: [(&str, &str); 7] = [
+    ("reshade", "ReShade"),
+    ("enbseries", "ENBSeries"),
+    ("dgvoodoo", "dgVoodoo"),
+    ("special k", "Special K"),
+    ("dxwrapper", "DxWrapper"),
+    ("d3d8to9", "d3d8to9"),
+    ("dxvk", "DXVK"),
+];
+
+/// Config files wrappers leave next to their DLL, used when the DLL itself is anonymous
+const COMPANION_FILES: [(&str, &str); 5] = [
+    ("reshade.ini", "ReShade"),
+    ("enbseries.ini", "ENBSeries"),
+    ("enblocal.ini", "ENBSeries"),
+    ("dgvoodoo.conf", "dgVoodoo"),
+    ("specialk.ini", "Special K"),
+];
+
+#[derive(Serialize)]
+pub struct Conflict {
+    pub file_name: String,
+    pub wrapper: String,
+    pub reason: String,
+}
+
+/// Looks for wrapper DLLs in the root of a game folder, skipping files Remix installed.
+pub fn scan(game_dir: &Path) -> Result<Vec<Conflict>> {
+    let ours = InstallRecord::load(game_dir)?
+        .map(|record| record.installed)
+        .unwrap_or_default();
+
+    let mut file_names = Vec::new();
+    for entry in fs::read_dir(game_dir).map_err(|e| Error::filesystem(game_dir, e))? {
+        let entry = entry.map_err(|e| Error::filesystem(game_dir, e))?;
+        if entry.path().is_file() {
+            file_names.push(entry.file_name().to_string_lossy().to_string());
+        }
+    }
+    file_names.sort();
+    let companion = file_names.iter().find_map(|name| {
+        let lower = name.to_ascii_lowercase();
+        COMPANION_FILES
+            .iter()
+            .find(|(file, _)| *file == lower)
+            .map(|(_, wrapper)| (*wrapper, name.clone()))
+    });
+
+    let mut conflicts = Vec::new();
+    for file_name in &file_names {
+        let lower = file_name.to_ascii_lowercase();
+        if !lower.ends_with(".dll") || ours.contains(file_name) {
+            continue;
+        }
+        let path = game_dir.join(file_name);
+        let Ok(pe) = PeFile::open(&path) else {
+            continue;
+        };
+        let is_proxy = PROXY_DLLS.contains(&lower.as_str());
+
+        let identified = identify_by_version(&pe)
+            .or_else(|| is_proxy.then(|| identify_by_hash(&path)).flatten())
+            .or_else(|| is_proxy.then(|| identify_by_contents(&pe)).flatten())
+            .or_else(|| {
+                let (wrapper, companion_file) = companion.as_ref()?;
+                is_proxy.then(|| {
+                    (
+                        wrapper.to_string(),
+                        format!("found next to {}", companion_file),
+                    )
+                })
+            });
+
+        let conflict = match identified {
+            Some((wrapper, reason)) => Conflict {
+                file_name: file_name.clone(),
+                wrapper,
+                reason,
+            },
+            None if REPLACED_BY_REMIX.contains(&lower.as_str()) => Conflict {
+                file_name: file_name.clone(),
+                wrapper: "Unknown Direct3D wrapper".to_string(),
+                reason: "not a system DLL".to_string(),
+            },
+            None => continue,
+        };
+        conflicts.push(conflict);
+    }
+    Ok(conflicts)
+}
+
+fn identify_by_version(pe: &PeFile) -> Option<(String, String)> {
+    pe.version_strings()
+        .into_iter()
+        .filter(|(key, _)| {
+            matches!(
+                key.as_str(),
+                "ProductName" | "FileDescription" | "CompanyName" | "InternalName"
+            )
+        })
+        .find_map(|(key, value)| {
+            let lower = value.to_ascii_lowercase();
+            // dxvk-remix's own binaries call themselves DXVK too
+            if lower.contains("remix") {
+                return None;
+            }
+            KNOWN_WRAPPERS
+                .iter()
+                .find(|(fragment, _)| lower.contains(fragment))
+                .map(|(_, wrapper)| (wrapper.to_string(), format!("{} \"{}\"", key, value)))
+        })
+}
+
+fn identify_by_hash(path: &Path) -> Option<(String, String)> {
+    let sha256 = sha256_file(path).ok()?;
+    catalog::get()
+        .wrapper_hashes
+        .iter()
+        .find(|known| known.sha256.eq_ignore_ascii_case(&sha256))
+        .map(|known| (known.wrapper.clone(), "known file hash".to_string()))
+}
+
+fn identify_by_contents(pe: &PeFile) -> Option<(String, String)> {
+    if pe.contains_marker("remix") {
+        return None;
+    }
+    KNOWN_WRAPPERS
+        .iter()
+        .find(|(fragment, _)| pe.contains_marker(fragment))
+        .map(|(fragment, wrapper)| (wrapper.to_string(), format!("contains \"{}\"", fragment)))
+}
+
+pub fn print_conflicts(conflicts: &[Conflict], reporter: &mut dyn Reporter) {
+    reporter.warning("Found other graphics wrappers that can stop RTX Remix from loading:");
+    for conflict in conflicts {
+        report!(
+            reporter,
+            "  {} {} ({})",
+            conflict.file_name.yellow(),
+            conflict.wrapper,
+            conflict.reason
+        );
+    }
+}
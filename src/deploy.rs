@@ -0,0 +1,350 @@
+//! Installing a downloaded Remix package into a game folder.
+//!
+//! Files in the game folder that would be overwritten and were not placed there by a
+//! previous install are moved into `.remix-backup` along with their original hashes,
+//! as are conflicting wrappers the user chose to move aside, and are put back on
+//! uninstall or when an install fails partway through.
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use colored::*;
+use serde::{Deserialize, Serialize};
+
+use crate::config;
+use crate::error::Error;
+use crate::files::{list_files, path_key, sha256_file};
+use crate::progress::Reporter;
+
+pub const BACKUP_DIR: &str = ".remix-backup";
+const RECORD_FILE: &str = "install.json";
+const FILES_DIR: &str = "files";
+const ROLLBACK_DIR: &str = "rollback";
+
+/// What the downloader has placed in a game folder and what it moved aside to do so.
+#[derive(Default, Serialize, Deserialize)]
+pub struct InstallRecord {
+    /// Files written by the downloader, relative to the game folder
+    pub installed: Vec<String>,
+    /// Original game or mod files that were replaced
+    pub backups: Vec<BackupEntry>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct BackupEntry {
+    pub path: String,
+    pub sha256: String,
+}
+
+impl InstallRecord {
+    pub fn load(game_dir: &Path) -> Result<Option<Self>> {
+        let record_path = game_dir.join(BACKUP_DIR).join(RECORD_FILE);
+        if !record_path.exists() {
+            return Ok(None);
+        }
+        let contents =
+            fs::read_to_string(&record_path).map_err(|e| Error::filesystem(&record_path, e))?;
+        let record = serde_json::from_str(&contents).map_err(|e| {
+            Error::invalid(format!("Could not parse {}: {}", record_path.display(), e))
+        })?;
+        Ok(Some(record))
+    }
+
+    fn save(&self, game_dir: &Path) -> Result<()> {
+        let backup_dir = game_dir.join(BACKUP_DIR);
+        fs::create_dir_all(&backup_dir).map_err(|e| Error::filesystem(&backup_dir, e))?;
+        let record_path = backup_dir.join(RECORD_FILE);
+        fs::write(&record_path, serde_json::to_string_pretty(self)?)
+            .map_err(|e| Error::filesystem(&record_path, e))?;
+        Ok(())
+    }
+}
+
+/// Changes made by an in-progress deploy, kept so a failure can be undone.
+#[derive(Default)]
+struct Journal {
+    copied: Vec<PathBuf>,
+    /// Conflicting wrappers moved aside
+    set_aside: Vec<PathBuf>,
+    backed_up: Vec<PathBuf>,
+    replaced: Vec<PathBuf>,
+}
+
+/// Copies the package into the game folder, first moving the files in `set_aside`, such
+/// as conflicting wrappers, into the backup.
+pub fn deploy_to_game_dir(
+    package_path: &Path,
+    game_dir: &Path,
+    set_aside: &[String],
+    reporter: &mut dyn Reporter,
+) -> Result<()> {
+    report!(
+        reporter,
+        "{}",
+        format!("Installing into {}...", game_dir.display()).cyan()
+    );
+    if !game_dir.is_dir() {
+        anyhow::bail!(Error::invalid(format!(
+            "Game folder {} does not exist",
+            game_dir.display()
+        )));
+    }
+
+    let mut record = InstallRecord::load(game_dir)?.unwrap_or_default();
+    let previous: HashSet<String> = record.installed.iter().cloned().collect();
+    let package_files = list_files(package_path).map_err(|e| Error::filesystem(package_path, e))?;
+
+    // Configs from an earlier install may have been edited, so merge rather than replace them
+    let saved_configs: Vec<config::SavedConfig> = config::capture_configs(game_dir)
+        .into_iter()
+        .filter(|saved| previous.contains(saved.path))
+        .collect();
+    let merged_configs = config::merge_saved(&saved_configs, package_path, reporter);
+
+    let mut journal = Journal::default();
+    let copied = set_aside_files(game_dir, set_aside, &mut record, &mut journal).and_then(|_| {
+        copy_package(
+            package_path,
+            game_dir,
+            &package_files,
+            &previous,
+            &merged_configs,
+            &mut record,
+            &mut journal,
+        )
+    });
+    if let Err(e) = copied {
+        reporter.warning("Install failed, restoring the game folder...");
+        rollback(game_dir, &journal)?;
+        return Err(e);
+    }
+    for relative in &journal.set_aside {
+        report!(
+            reporter,
+            "{}",
+            format!("Moved {} to {}", path_key(relative), BACKUP_DIR).cyan()
+        );
+    }
+    for relative in &journal.backed_up {
+        report!(
+            reporter,
+            "{}",
+            format!("Backed up existing {}", path_key(relative)).yellow()
+        );
+    }
+
+    // Drop files left over from a previous install that the new package no longer ships,
+    // except configs the user may have edited, which stay for a later install to merge
+    let mut installed: Vec<String> = package_files.iter().map(|p| path_key(p)).collect();
+    for stale in previous.iter().filter(|key| !installed.contains(key)) {
+        if config::MERGED_CONFIGS.contains(&stale.as_str()) {
+            continue;
+        }
+        let stale_path = game_dir.join(stale);
+        if stale_path.exists() {
+            fs::remove_file(&stale_path).map_err(|e| Error::filesystem(&stale_path, e))?;
+        }
+        remove_empty_parents(game_dir, &stale_path);
+        restore_backup(game_dir, &mut record, stale, reporter)?;
+    }
+
+    fs::remove_dir_all(game_dir.join(BACKUP_DIR).join(ROLLBACK_DIR)).ok();
+    let kept_configs: Vec<String> = config::MERGED_CONFIGS
+        .iter()
+        .filter(|path| previous.contains(**path) && !installed.iter().any(|key| key == *path))
+        .map(|path| path.to_string())
+        .collect();
+    installed.extend(kept_configs);
+    record.installed = installed;
+    record.save(game_dir)?;
+
+    if !journal.backed_up.is_empty() {
+        report!(
+            reporter,
+            "{}",
+            format!(
+                "Backed up {} existing files to {}",
+                journal.backed_up.len(),
+                BACKUP_DIR
+            )
+            .cyan()
+        );
+    }
+    report!(
+        reporter,
+        "{}",
+        format!("Installed {} files", record.installed.len()).green()
+    );
+    Ok(())
+}
+
+/// Moves files out of the game folder into the backup, so uninstall puts them back.
+fn set_aside_files(
+    game_dir: &Path,
+    keys: &[String],
+    record: &mut InstallRecord,
+    journal: &mut Journal,
+) -> Result<()> {
+    let backup_dir = game_dir.join(BACKUP_DIR);
+    for key in keys {
+        let file_path = game_dir.join(key);
+        let sha256 = sha256_file(&file_path)?;
+        move_file(&file_path, &backup_dir.join(FILES_DIR).join(key))?;
+        record.backups.push(BackupEntry {
+            path: key.clone(),
+            sha256,
+        });
+        journal.set_aside.push(PathBuf::from(key));
+    }
+    Ok(())
+}
+
+fn copy_package(
+    package_path: &Path,
+    game_dir: &Path,
+    package_files: &[PathBuf],
+    previous: &HashSet<String>,
+    merged_configs: &[(&str, String)],
+    record: &mut InstallRecord,
+    journal: &mut Journal,
+) -> Result<()> {
+    let backup_dir = game_dir.join(BACKUP_DIR);
+    for relative in package_files {
+        let key = path_key(relative);
+        let dest_path = game_dir.join(relative);
+
+        if dest_path.exists() {
+            if previous.contains(&key) {
+                // Ours from an earlier install, only kept until this one succeeds
+                move_file(&dest_path, &backup_dir.join(ROLLBACK_DIR).join(relative))?;
+                journal.replaced.push(relative.clone());
+            } else {
+                let sha256 = sha256_file(&dest_path)?;
+                move_file(&dest_path, &backup_dir.join(FILES_DIR).join(relative))?;
+                record.backups.push(BackupEntry {
+                    path: key.clone(),
+                    sha256,
+                });
+                journal.backed_up.push(relative.clone());
+            }
+        }
+
+        if let Some(parent) = dest_path.parent() {
+            fs::create_dir_all(parent).map_err(|e| Error::filesystem(parent, e))?;
+        }
+        match merged_configs.iter().find(|(path, _)| *path == key) {
+            Some((_, text)) => fs::write(&dest_path, text).map(|_| ()),
+            None => fs::copy(package_path.join(relative), &dest_path).map(|_| ()),
+        }
+        .map_err(|e| Error::filesystem(&dest_path, e))?;
+        journal.copied.push(relative.clone());
+    }
+    Ok(())
+}
+
+fn rollback(game_dir: &Path, journal: &Journal) -> Result<()> {
+    let backup_dir = game_dir.join(BACKUP_DIR);
+    for relative in &journal.copied {
+        fs::remove_file(game_dir.join(relative)).ok();
+    }
+    for relative in &journal.replaced {
+        move_file(
+            &backup_dir.join(ROLLBACK_DIR).join(relative),
+            &game_dir.join(relative),
+        )?;
+    }
+    for relative in journal.backed_up.iter().chain(&journal.set_aside) {
+        move_file(
+            &backup_dir.join(FILES_DIR).join(relative),
+            &game_dir.join(relative),
+        )?;
+    }
+    fs::remove_dir_all(backup_dir.join(ROLLBACK_DIR)).ok();
+    Ok(())
+}
+
+pub fn uninstall(game_dir: &Path, reporter: &mut dyn Reporter) -> Result<()> {
+    let mut record = InstallRecord::load(game_dir)?.ok_or_else(|| {
+        Error::not_found(format!("a Remix install record in {}", game_dir.display()))
+    })?;
+
+    report!(
+        reporter,
+        "{}",
+        format!("Removing Remix from {}...", game_dir.display()).cyan()
+    );
+    for key in &record.installed {
+        let installed_path = game_dir.join(key);
+        if installed_path.exists() {
+            fs::remove_file(&installed_path).map_err(|e| Error::filesystem(&installed_path, e))?;
+        }
+        remove_empty_parents(game_dir, &installed_path);
+    }
+
+    let keys: Vec<String> = record.backups.iter().map(|b| b.path.clone()).collect();
+    for key in &keys {
+        restore_backup(game_dir, &mut record, key, reporter)?;
+    }
+
+    let backup_dir = game_dir.join(BACKUP_DIR);
+    fs::remove_dir_all(&backup_dir).map_err(|e| Error::filesystem(&backup_dir, e))?;
+    report!(
+        reporter,
+        "{}",
+        format!(
+            "Removed {} files and restored {} original files",
+            record.installed.len(),
+            keys.len()
+        )
+        .green()
+    );
+    Ok(())
+}
+
+/// Moves a backed up original back into place and drops it from the record.
+fn restore_backup(
+    game_dir: &Path,
+    record: &mut InstallRecord,
+    key: &str,
+    reporter: &mut dyn Reporter,
+) -> Result<()> {
+    let Some(index) = record.backups.iter().position(|b| b.path == key) else {
+        return Ok(());
+    };
+    let entry = record.backups.remove(index);
+    let stored_path = game_dir.join(BACKUP_DIR).join(FILES_DIR).join(key);
+    if !stored_path.exists() {
+        reporter.warning(&format!("Backup of {} is missing, skipping", key));
+        return Ok(());
+    }
+    if sha256_file(&stored_path)? != entry.sha256 {
+        reporter.warning(&format!(
+            "Backup of {} does not match its recorded hash",
+            key
+        ));
+    }
+    move_file(&stored_path, &game_dir.join(key))?;
+    report!(reporter, "{}", format!("Restored original {}", key).cyan());
+    Ok(())
+}
+
+fn move_file(from: &Path, to: &Path) -> Result<()> {
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent).map_err(|e| Error::filesystem(parent, e))?;
+    }
+    fs::rename(from, to)
+        .map_err(|e| Error::filesystem(from, e))
+        .with_context(|| format!("Could not move {} to {}", from.display(), to.display()))
+}
+
+fn remove_empty_parents(game_dir: &Path, path: &Path) {
+    let mut current = path.parent();
+    while let Some(dir) = current {
+        if dir == game_dir || fs::remove_dir(dir).is_err() {
+            break;
+        }
+        current = dir.parent();
+    }
+}
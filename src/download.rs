@@ -0,0 +1,540 @@
+//! Fetching and unpacking the files of a build: packages, the bridge, the dxwrapper D3D8
+//! layer, configs, licenses and symbols. Progress goes to the `Reporter` the `Downloader`
+//! was made with.
+
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::Path;
+
+use anyhow::Result;
+use colored::*;
+use sha2::{Digest, Sha256};
+
+use crate::catalog::{self, Repo};
+use crate::error::Error;
+use crate::files;
+use crate::http::{self, Http};
+use crate::layout;
+use crate::lockfile::DownloadLog;
+use crate::manifest::BuildSource;
+use crate::progress::{Phase, Reporter};
+use crate::{pe, plan, Arch};
+
+/// Downloads the files of an install, logging their hashes for the lockfile.
+pub struct Downloader<'a> {
+    pub client: &'a dyn Http,
+    pub log: DownloadLog,
+    /// Everything fetched and changed so far, for --dry-run
+    pub plan: plan::Plan,
+    pub reporter: &'a mut dyn Reporter,
+    /// Only look up sizes and file lists, leaving every file and zip entry empty
+    pub dry_run: bool,
+    /// Phases begun so far
+    step: usize,
+}
+
+impl<'a> Downloader<'a> {
+    pub fn new(client: &'a dyn Http, log: DownloadLog, reporter: &'a mut dyn Reporter) -> Self {
+        Self {
+            client,
+            log,
+            plan: plan::Plan::default(),
+            reporter,
+            dry_run: false,
+            step: 0,
+        }
+    }
+
+    /// Reports the next phase of assembling the build.
+    pub fn phase(&mut self, phase: Phase) {
+        self.step += 1;
+        self.reporter.phase(phase, self.step);
+    }
+
+    /// Records a change made after extraction.
+    pub fn change(&mut self, change: String) {
+        self.reporter.changed(&change);
+        self.plan.change(change);
+    }
+}
+
+/// Downloads `url` to `dest`, recording its hash in the download log. A dry run only asks
+/// for the size and leaves `dest` empty.
+pub fn download_file(downloader: &mut Downloader, url: &str, dest: &Path) -> Result<()> {
+    if !downloader.dry_run {
+        return transfer(downloader, url, dest);
+    }
+    let size = http::content_length(downloader.client, url).unwrap_or(0);
+    fs::File::create(dest).map_err(|e| Error::filesystem(dest, e))?;
+    downloader.plan.downloads.push((url.to_string(), size));
+    Ok(())
+}
+
+/// Downloads a zip to `dest`. A dry run reads the zip's list of files through range
+/// requests instead and writes a zip of empty files with the same names, falling back
+/// to the whole download when the server can't send ranges.
+pub fn download_zip(downloader: &mut Downloader, url: &str, dest: &Path) -> Result<()> {
+    if !downloader.dry_run {
+        return transfer(downloader, url, dest);
+    }
+    let size = http::content_length(downloader.client, url);
+    let names = size.map(|size| zip_names(http::RemoteFile::new(downloader.client, url, size)));
+    match (size, names) {
+        (Some(size), Some(Ok(names))) => {
+            write_listing(dest, &names)?;
+            downloader.plan.downloads.push((url.to_string(), size));
+            Ok(())
+        }
+        _ => {
+            report!(
+                downloader.reporter,
+                "{}",
+                format!(
+                    "Could not list the files in {} remotely, downloading it",
+                    url
+                )
+                .yellow()
+            );
+            transfer(downloader, url, dest)
+        }
+    }
+}
+
+/// The names in a zip, read from its central directory.
+fn zip_names(reader: impl io::Read + io::Seek) -> zip::result::ZipResult<Vec<String>> {
+    let archive = zip::ZipArchive::new(reader)?;
+    let mut names: Vec<String> = archive.file_names().map(str::to_string).collect();
+    names.sort();
+    Ok(names)
+}
+
+/// Writes a zip holding an empty entry for each of `names`, standing in for a package
+/// in a dry run.
+fn write_listing(dest: &Path, names: &[String]) -> Result<()> {
+    let file = fs::File::create(dest).map_err(|e| Error::filesystem(dest, e))?;
+    let mut writer = zip::ZipWriter::new(file);
+    let options =
+        zip::write::FileOptions::default().compression_method(zip::CompressionMethod::Stored);
+    for name in names {
+        if name.ends_with('/') {
+            writer.add_directory(name.as_str(), options)
+        } else {
+            writer.start_file(name.as_str(), options)
+        }
+        .map_err(|e| Error::extraction(dest, e))?;
+    }
+    writer.finish().map_err(|e| Error::extraction(dest, e))?;
+    Ok(())
+}
+
+/// Downloads `url` to `dest` in full.
+fn transfer(downloader: &mut Downloader, url: &str, dest: &Path) -> Result<()> {
+    let mut response = http::get(downloader.client, url)?;
+    let total = response.content_length.unwrap_or(0);
+    downloader.reporter.download_started(url, total);
+
+    let mut file = fs::File::create(dest).map_err(|e| Error::filesystem(dest, e))?;
+    let mut hasher = Sha256::new();
+
+    let mut buffer = [0; 8192];
+    let mut downloaded = 0;
+    loop {
+        let size = response
+            .body
+            .read(&mut buffer)
+            .map_err(|e| Error::network(url, e))?;
+        if size == 0 {
+            break;
+        }
+        file.write_all(&buffer[..size])
+            .map_err(|e| Error::filesystem(dest, e))?;
+        hasher.update(&buffer[..size]);
+        downloaded += size as u64;
+        downloader
+            .reporter
+            .download_progress(url, downloaded, total);
+    }
+
+    downloader.reporter.download_finished(url, downloaded);
+    downloader
+        .plan
+        .downloads
+        .push((url.to_string(), downloaded));
+    downloader
+        .log
+        .record(url, format!("{:x}", hasher.finalize()))
+}
+
+/// Downloads a package zip, or copies it when installing from a local file.
+pub fn fetch_package(downloader: &mut Downloader, source: &BuildSource, dest: &Path) -> Result<()> {
+    let Some(local_zip) = &source.local_zip else {
+        return download_zip(downloader, &source.download_url, dest);
+    };
+    if downloader.dry_run {
+        let size = fs::metadata(local_zip)
+            .map_err(|e| Error::filesystem(local_zip, e))?
+            .len();
+        let names =
+            zip_names(fs::File::open(local_zip).map_err(|e| Error::filesystem(local_zip, e))?)
+                .map_err(|e| Error::extraction(local_zip, e))?;
+        write_listing(dest, &names)?;
+        downloader
+            .plan
+            .downloads
+            .push((local_zip.display().to_string(), size));
+        return Ok(());
+    }
+    let size = fs::copy(local_zip, dest).map_err(|e| Error::filesystem(local_zip, e))?;
+    downloader
+        .plan
+        .downloads
+        .push((local_zip.display().to_string(), size));
+    downloader
+        .log
+        .record(&source.download_url, files::sha256_file(dest)?)
+}
+
+/// Replaces the bridge files of an extracted x86 package with those from another package.
+pub fn overlay_bridge(
+    downloader: &mut Downloader,
+    bridge: &BuildSource,
+    final_path: &Path,
+) -> Result<()> {
+    report!(
+        downloader.reporter,
+        "{}",
+        format!("Taking the bridge from {}", bridge.package_name).cyan()
+    );
+    let bridge_zip = final_path.join("bridge-package.zip");
+    fetch_package(downloader, bridge, &bridge_zip)?;
+
+    // Unified packages already use the install layout, bridge-remix artifacts don't,
+    // so place each file by name rather than by its path in the zip
+    let mut archive = files::open_zip(&bridge_zip)?;
+    let mut replaced = 0;
+    for i in 0..archive.len() {
+        let mut entry = archive
+            .by_index(i)
+            .map_err(|e| Error::extraction(&bridge_zip, e))?;
+        let Some(entry_path) = entry.enclosed_name().map(Path::to_path_buf) else {
+            continue;
+        };
+        let file_name = entry_path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string();
+        let Some(bridge_file) = catalog::get()
+            .bridge_files
+            .iter()
+            .find(|bridge_file| bridge_file.name.eq_ignore_ascii_case(&file_name))
+        else {
+            continue;
+        };
+        let (name, destination) = (&bridge_file.name, &bridge_file.destination);
+
+        let mut data = Vec::new();
+        entry
+            .read_to_end(&mut data)
+            .map_err(|e| Error::extraction(&bridge_zip, e.into()))?;
+        // A dry run only has the names, so it can't tell the architectures apart
+        if name.ends_with(".dll")
+            && !downloader.dry_run
+            && !pe::PeFile::parse(data.clone()).is_ok_and(|pe| pe.machine() == pe::Machine::X86)
+        {
+            continue;
+        }
+        let dest_dir = final_path.join(destination);
+        fs::create_dir_all(&dest_dir).map_err(|e| Error::filesystem(&dest_dir, e))?;
+        let dest_path = dest_dir.join(name);
+        fs::write(&dest_path, data).map_err(|e| Error::filesystem(&dest_path, e))?;
+        downloader.change(format!(
+            "replace {} with the one from {}",
+            Path::new(destination).join(name).display(),
+            bridge.package_name
+        ));
+        replaced += 1;
+    }
+    drop(archive);
+    fs::remove_file(&bridge_zip).map_err(|e| Error::filesystem(&bridge_zip, e))?;
+
+    if replaced == 0 {
+        anyhow::bail!(Error::not_found(format!(
+            "the bridge in {}",
+            bridge.package_name
+        )));
+    }
+    Ok(())
+}
+
+pub fn download_additional_files(
+    downloader: &mut Downloader,
+    source: &BuildSource,
+    final_path: &Path,
+) -> Result<()> {
+    report!(
+        downloader.reporter,
+        "{}",
+        "Downloading additional files".cyan()
+    );
+    for file in &catalog::get().additional_files {
+        let dest_path = final_path.join(&file.destination).join(&file.name);
+        let url = pinned_raw_url(downloader, source, file.repo, &file.path);
+        download_file(downloader, &url, &dest_path)?;
+        report!(
+            downloader.reporter,
+            "{}",
+            format!("Downloaded {}", file.name).green()
+        );
+    }
+    Ok(())
+}
+
+pub fn download_licenses(
+    downloader: &mut Downloader,
+    source: &BuildSource,
+    final_path: &Path,
+) -> Result<()> {
+    report!(
+        downloader.reporter,
+        "{}",
+        "Downloading license files".cyan()
+    );
+    let arch = if source.is_x86 { Arch::X86 } else { Arch::X64 };
+    for license in &catalog::get().licenses {
+        if license.arch.is_some_and(|only| only != arch) {
+            continue;
+        }
+        let dest_path = final_path.join(&license.name);
+        let url = pinned_raw_url(downloader, source, license.repo, &license.path);
+        download_file(downloader, &url, &dest_path)?;
+    }
+    report!(
+        downloader.reporter,
+        "{}",
+        "License files downloaded".green()
+    );
+    Ok(())
+}
+
+pub fn raw_url(repo: Repo, git_ref: &str, path: &str) -> String {
+    format!(
+        "{}/{}/{}/{}",
+        catalog::get().hosts.raw,
+        catalog::get().repo(repo),
+        git_ref,
+        path
+    )
+}
+
+/// The tag or commit of `repo` that a build was made from, when known.
+fn source_ref(source: &BuildSource, repo: Repo) -> Option<&str> {
+    if let (Some(bridge), Repo::Bridge) = (&source.bridge, repo) {
+        return source_ref(bridge, repo);
+    }
+    if source.is_stable {
+        // Stable releases tag all three repos with the same name
+        return source.release_tag.as_deref();
+    }
+    match repo {
+        Repo::Dxvk => source.head_sha.as_deref(),
+        Repo::Bridge => source.bridge_sha.as_deref(),
+        Repo::Rtx => None,
+    }
+}
+
+/// URL of a file in `repo` at the build's own tag or commit, falling back to main when
+/// the build doesn't say which one it came from or the file isn't there.
+fn pinned_raw_url(
+    downloader: &mut Downloader,
+    source: &BuildSource,
+    repo: Repo,
+    path: &str,
+) -> String {
+    let main_url = raw_url(repo, "main", path);
+    let Some(git_ref) = source_ref(source, repo) else {
+        // rtx-remix only hosts the license, which isn't tied to a dxvk-remix commit
+        if !source.is_stable && repo == Repo::Rtx {
+            return main_url;
+        }
+        downloader.reporter.warning(&format!(
+            "Could not tell which {} commit this build uses, using {} from main",
+            catalog::get().repo(repo),
+            path
+        ));
+        return main_url;
+    };
+
+    let pinned_url = raw_url(repo, git_ref, path);
+    if http::exists(downloader.client, &pinned_url) {
+        pinned_url
+    } else {
+        downloader.reporter.warning(&format!(
+            "{} not found at {}@{}, using the version from main",
+            path,
+            catalog::get().repo(repo),
+            git_ref
+        ));
+        main_url
+    }
+}
+
+/// Extracts a package zip into `final_path`, recording the files it held.
+pub fn extract_package(
+    downloader: &mut Downloader,
+    name: &str,
+    zip_path: &Path,
+    final_path: &Path,
+) -> Result<()> {
+    let mut archive = files::open_zip(zip_path)?;
+    let total = archive.len();
+    let mut files = Vec::new();
+    for i in 0..total {
+        let mut entry = archive
+            .by_index(i)
+            .map_err(|e| Error::extraction(zip_path, e))?;
+        // Skip entries that would land outside the folder
+        if let Some(relative) = entry.enclosed_name().map(Path::to_path_buf) {
+            let dest = final_path.join(relative);
+            if entry.is_dir() {
+                fs::create_dir_all(&dest).map_err(|e| Error::filesystem(&dest, e))?;
+            } else {
+                if let Some(parent) = dest.parent() {
+                    fs::create_dir_all(parent).map_err(|e| Error::filesystem(parent, e))?;
+                }
+                let mut file = fs::File::create(&dest).map_err(|e| Error::filesystem(&dest, e))?;
+                io::copy(&mut entry, &mut file).map_err(|e| Error::filesystem(&dest, e))?;
+                files.push(entry.name().to_string());
+            }
+        }
+        downloader.reporter.extracted(name, i + 1, total);
+    }
+    downloader.plan.extracted.push((name.to_string(), files));
+    Ok(())
+}
+
+pub fn download_and_extract_dx8_binaries(
+    downloader: &mut Downloader,
+    rules: &layout::Rules,
+    source: &BuildSource,
+    final_path: &Path,
+) -> Result<()> {
+    report!(downloader.reporter, "{}", "Downloading dx8 binaries".cyan());
+    let catalog = catalog::get();
+    let dx8_zip_path = final_path.join("dx8_binaries.zip");
+    let binaries_url = catalog.on_hosts(&catalog.dxwrapper.binaries_url);
+    download_zip(downloader, &binaries_url, &dx8_zip_path)?;
+
+    report!(downloader.reporter, "{}", "Extracting dx8 binaries".cyan());
+    extract_package(
+        downloader,
+        "dxwrapper dx8 binaries",
+        &dx8_zip_path,
+        final_path,
+    )?;
+
+    // Rename d3d8.dll to d3d8_off.dll and drop d3d8to9.dll
+    rules.apply(layout::Stage::Dx8, source, final_path, &mut |change| {
+        downloader.change(change)
+    })?;
+
+    // Download the dxwrapper license specifically here since it's related to these binaries
+    report!(
+        downloader.reporter,
+        "{}",
+        "Downloading dxwrapper license".cyan()
+    );
+    let license_dest_path = final_path.join("ThirdPartyLicenses-dxwrapper.txt");
+    let license_url = catalog.on_hosts(&catalog.dxwrapper.license_url);
+    download_file(downloader, &license_url, &license_dest_path)?;
+
+    report!(
+        downloader.reporter,
+        "{}",
+        "Cleaning up dx8 binaries zip file".cyan()
+    );
+    fs::remove_file(&dx8_zip_path).map_err(|e| Error::filesystem(&dx8_zip_path, e))?;
+
+    Ok(())
+}
+
+/// Downloads a symbols package and puts each PDB next to the binary it belongs to.
+pub fn download_symbols(
+    downloader: &mut Downloader,
+    symbols_url: &str,
+    final_path: &Path,
+) -> Result<()> {
+    report!(
+        downloader.reporter,
+        "{}",
+        "Downloading debug symbols".cyan()
+    );
+    let symbols_zip = final_path.join("symbols.zip");
+    download_zip(downloader, symbols_url, &symbols_zip)?;
+
+    // Binaries by lowercase file stem, so foo.pdb lands wherever foo.dll or foo.exe is.
+    // When a name appears twice, as d3d9.dll does on x86, the .trex runtime copy sorts first.
+    let mut binaries = std::collections::HashMap::new();
+    for relative in files::list_files(final_path).map_err(|e| Error::filesystem(final_path, e))? {
+        let is_binary = relative
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("dll") || ext.eq_ignore_ascii_case("exe"));
+        if let (true, Some(stem)) = (is_binary, relative.file_stem()) {
+            let dir = relative.parent().unwrap_or(Path::new("")).to_path_buf();
+            binaries
+                .entry(stem.to_string_lossy().to_ascii_lowercase())
+                .or_insert(dir);
+        }
+    }
+
+    let mut archive = files::open_zip(&symbols_zip)?;
+    let mut placed = 0;
+    for i in 0..archive.len() {
+        let mut entry = archive
+            .by_index(i)
+            .map_err(|e| Error::extraction(&symbols_zip, e))?;
+        let Some(entry_path) = entry.enclosed_name().map(Path::to_path_buf) else {
+            continue;
+        };
+        if !entry_path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("pdb"))
+        {
+            continue;
+        }
+        let stem = entry_path
+            .file_stem()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_ascii_lowercase();
+        // Keep the package's own location when its binary is already there
+        let in_place = ["dll", "exe"]
+            .iter()
+            .any(|ext| final_path.join(entry_path.with_extension(ext)).is_file());
+        let dest_path = match binaries.get(&stem) {
+            Some(dir) if !in_place => final_path
+                .join(dir)
+                .join(entry_path.file_name().unwrap_or_default()),
+            _ => final_path.join(&entry_path),
+        };
+        if let Some(parent) = dest_path.parent() {
+            fs::create_dir_all(parent).map_err(|e| Error::filesystem(parent, e))?;
+        }
+        let mut file =
+            fs::File::create(&dest_path).map_err(|e| Error::filesystem(&dest_path, e))?;
+        io::copy(&mut entry, &mut file).map_err(|e| Error::filesystem(&dest_path, e))?;
+        if let Ok(relative) = dest_path.strip_prefix(final_path) {
+            downloader.change(format!("add symbols {}", files::path_key(relative)));
+        }
+        placed += 1;
+    }
+    drop(archive);
+    fs::remove_file(&symbols_zip).map_err(|e| Error::filesystem(&symbols_zip, e))?;
+
+    report!(
+        downloader.reporter,
+        "{}",
+        format!("Added {} symbol files", placed).green()
+    );
+    Ok(())
+}
@@ -0,0 +1,125 @@
+//! Errors callers can tell apart. Functions still return `anyhow::Result`, with one of
+//! these as the root cause, so `error.downcast_ref::<Error>()` tells what went wrong and
+//! the URL, artifact or path involved.
+
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use reqwest::StatusCode;
+
+use crate::output::Exit;
+
+#[derive(Debug)]
+pub enum Error {
+    /// A host couldn't be reached or the connection failed mid-transfer
+    Network {
+        url: String,
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+    /// A request got an unexpected HTTP status
+    HttpStatus { url: String, status: StatusCode },
+    /// The GitHub API rate limit is used up until `reset` (Unix time), if GitHub said
+    RateLimited { url: String, reset: Option<u64> },
+    /// A release, run, artifact, commit or file doesn't exist
+    NotFound { what: String },
+    /// A download or file doesn't match the hash it should have
+    Integrity { what: String, reason: String },
+    /// A package zip couldn't be read or extracted
+    Extraction {
+        path: PathBuf,
+        source: zip::result::ZipError,
+    },
+    /// Reading or writing a file failed
+    Filesystem { path: PathBuf, source: io::Error },
+    /// Invalid arguments, input, profiles or game folder
+    Invalid { message: String },
+}
+
+impl Error {
+    pub fn not_found(what: impl Into<String>) -> Self {
+        Self::NotFound { what: what.into() }
+    }
+
+    pub fn integrity(what: impl Into<String>, reason: impl Into<String>) -> Self {
+        Self::Integrity {
+            what: what.into(),
+            reason: reason.into(),
+        }
+    }
+
+    pub fn invalid(message: impl Into<String>) -> Self {
+        Self::Invalid {
+            message: message.into(),
+        }
+    }
+
+    pub fn filesystem(path: &Path, source: io::Error) -> Self {
+        Self::Filesystem {
+            path: path.to_path_buf(),
+            source,
+        }
+    }
+
+    pub fn extraction(path: &Path, source: zip::result::ZipError) -> Self {
+        Self::Extraction {
+            path: path.to_path_buf(),
+            source,
+        }
+    }
+
+    pub fn network(url: &str, source: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> Self {
+        Self::Network {
+            url: url.to_string(),
+            source: source.into(),
+        }
+    }
+
+    /// The exit code the program ends with when this is the cause.
+    pub fn exit(&self) -> Exit {
+        match self {
+            Self::Network { .. } | Self::HttpStatus { .. } | Self::RateLimited { .. } => {
+                Exit::Network
+            }
+            Self::Integrity { .. } => Exit::Integrity,
+            Self::Invalid { .. } => Exit::UserError,
+            Self::NotFound { .. } | Self::Extraction { .. } | Self::Filesystem { .. } => {
+                Exit::Failure
+            }
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Network { url, source } => write!(f, "Could not reach {}: {}", url, source),
+            Self::HttpStatus { url, status } => write!(f, "{} returned {}", url, status),
+            Self::RateLimited { url, reset } => {
+                write!(f, "GitHub's API rate limit is used up ({})", url)?;
+                if let Some(reset) = reset {
+                    write!(f, ", it resets at Unix time {}", reset)?;
+                }
+                Ok(())
+            }
+            Self::NotFound { what } => write!(f, "Could not find {}", what),
+            Self::Integrity { what, reason } => write!(f, "{} {}", what, reason),
+            Self::Extraction { path, source } => {
+                write!(f, "Could not extract {}: {}", path.display(), source)
+            }
+            Self::Filesystem { path, source } => write!(f, "{}: {}", path.display(), source),
+            Self::Invalid { message } => f.write_str(message),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Network { source, .. } => Some(source.as_ref()),
+            Self::Extraction { source, .. } => Some(source),
+            Self::Filesystem { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
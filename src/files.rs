@@ -0,0 +1,58 @@
+//! Filesystem helpers shared by the install, backup and verification code.
+
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use sha2::{Digest, Sha256};
+
+use crate::error::Error;
+
+/// Returns the lowercase hex SHA-256 of a file on disk.
+pub fn sha256_file(path: &Path) -> Result<String> {
+    let mut file = fs::File::open(path).map_err(|e| Error::filesystem(path, e))?;
+    let mut hasher = Sha256::new();
+    let mut buffer = [0; 8192];
+    loop {
+        let size = file
+            .read(&mut buffer)
+            .map_err(|e| Error::filesystem(path, e))?;
+        if size == 0 {
+            break;
+        }
+        hasher.update(&buffer[..size]);
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Opens a package zip, failing with an `Error` that names it.
+pub fn open_zip(path: &Path) -> Result<zip::ZipArchive<fs::File>> {
+    let file = fs::File::open(path).map_err(|e| Error::filesystem(path, e))?;
+    Ok(zip::ZipArchive::new(file).map_err(|e| Error::extraction(path, e))?)
+}
+
+/// Lists every file below `root` as a path relative to it, in a stable order.
+pub fn list_files(root: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    list_files_recursive(root, root, &mut files)?;
+    files.sort();
+    Ok(files)
+}
+
+fn list_files_recursive(root: &Path, dir: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            list_files_recursive(root, &path, files)?;
+        } else if let Ok(relative) = path.strip_prefix(root) {
+            files.push(relative.to_path_buf());
+        }
+    }
+    Ok(())
+}
+
+/// Turns a relative path into the forward-slash form stored in install records.
+pub fn path_key(relative: &Path) -> String {
+    relative.to_string_lossy().replace('\\', "/")
+}
@@ -0,0 +1,109 @@
+//! Working out which graphics API a game renders with from the DLLs it imports.
+
+use std::collections::{BTreeSet, HashSet};
+use std::fs;
+use std::path::Path;
+
+use anyhow::Result;
+
+use crate::error::Error;
+use crate::pe::PeFile;
+
+/// DLLs Remix or an earlier install put next to the game, which wrap an API
+/// rather than tell us anything about the game itself
+const WRAPPER_DLLS: [&str; 3] = ["dxwrapper.dll", "d3d8_off.dll", "d3d8to9.dll"];
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum GraphicsApi {
+    D3D8,
+    D3D9,
+    D3D10,
+    D3D11,
+    D3D12,
+    OpenGl,
+    Vulkan,
+}
+
+impl GraphicsApi {
+    fn from_dll(name: &str) -> Option<Self> {
+        match name {
+            "d3d8.dll" => Some(Self::D3D8),
+            "d3d9.dll" => Some(Self::D3D9),
+            "d3d10.dll" | "d3d10_1.dll" => Some(Self::D3D10),
+            "d3d11.dll" => Some(Self::D3D11),
+            "d3d12.dll" => Some(Self::D3D12),
+            "opengl32.dll" => Some(Self::OpenGl),
+            "vulkan-1.dll" => Some(Self::Vulkan),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::D3D8 => "Direct3D 8",
+            Self::D3D9 => "Direct3D 9",
+            Self::D3D10 => "Direct3D 10",
+            Self::D3D11 => "Direct3D 11",
+            Self::D3D12 => "Direct3D 12",
+            Self::OpenGl => "OpenGL",
+            Self::Vulkan => "Vulkan",
+        }
+    }
+
+    /// Remix only hooks fixed-function era Direct3D
+    pub fn is_supported(self) -> bool {
+        matches!(self, Self::D3D8 | Self::D3D9)
+    }
+}
+
+/// Scans a game executable, and the game's own DLLs it links against, for graphics API imports.
+pub fn detect_graphics_apis(game_exe: &Path) -> Result<BTreeSet<GraphicsApi>> {
+    let game_dir = game_exe.parent().unwrap_or(Path::new("."));
+    let mut apis = BTreeSet::new();
+    let mut visited = HashSet::new();
+
+    let mut queue = vec![PeFile::open(game_exe)?];
+    while let Some(pe) = queue.pop() {
+        for dll in pe.imported_dlls() {
+            if let Some(api) = GraphicsApi::from_dll(&dll) {
+                apis.insert(api);
+                continue;
+            }
+            let local_path = game_dir.join(&dll);
+            if local_path.is_file() && visited.insert(dll) {
+                if let Ok(pe) = PeFile::open(&local_path) {
+                    queue.push(pe);
+                }
+            }
+        }
+    }
+
+    // Renderers loaded at runtime with LoadLibrary don't show up in any import
+    // table, so fall back to looking at every DLL the game ships with
+    if apis.is_empty() {
+        for entry in fs::read_dir(game_dir).map_err(|e| Error::filesystem(game_dir, e))? {
+            let path = entry.map_err(|e| Error::filesystem(game_dir, e))?.path();
+            let file_name = path
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_ascii_lowercase();
+            if !file_name.ends_with(".dll")
+                || visited.contains(&file_name)
+                || WRAPPER_DLLS.contains(&file_name.as_str())
+                || GraphicsApi::from_dll(&file_name).is_some()
+            {
+                continue;
+            }
+            if let Ok(pe) = PeFile::open(&path) {
+                apis.extend(
+                    pe.imported_dlls()
+                        .iter()
+                        .filter_map(|dll| GraphicsApi::from_dll(dll)),
+                );
+            }
+        }
+    }
+
+    Ok(apis)
+}
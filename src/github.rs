@@ -0,0 +1,286 @@
+//! Finding builds on GitHub: the latest stable release, and artifacts of the latest
+//! successful workflow run on a branch or pull request.
+
+use anyhow::Result;
+use colored::*;
+use serde_json::Value;
+
+use crate::catalog::{self, Repo};
+use crate::error::Error;
+use crate::http::{self, Http};
+use crate::progress::Reporter;
+
+/// A package found on GitHub for the selected stream
+pub struct Package {
+    pub name: String,
+    pub download_url: String,
+    pub release_tag: Option<String>,
+    pub run_id: Option<u64>,
+    pub artifact_id: Option<u64>,
+    pub head_sha: Option<String>,
+    pub bridge_sha: Option<String>,
+    /// Matching package of debug symbols, when one was published
+    pub symbols_url: Option<String>,
+}
+
+/// The package of the latest stable release for a build type, with its symbols if any.
+pub fn fetch_latest_stable_release(
+    client: &dyn Http,
+    build_type: &str,
+    reporter: &mut dyn Reporter,
+) -> Result<Package> {
+    report!(
+        reporter,
+        "{}",
+        "Fetching latest stable release information...".cyan()
+    );
+
+    let releases_url = format!(
+        "{}/repos/{}/releases/latest",
+        catalog::get().hosts.api,
+        catalog::get().repo(Repo::Rtx)
+    );
+    let response = http::get_json(client, &releases_url)?;
+
+    let asset = response["assets"]
+        .as_array()
+        .and_then(|assets| {
+            assets.iter().find(|asset| {
+                asset["name"].as_str().is_some_and(|name| {
+                    // Match the exact pattern: ends with build_type.zip
+                    // and explicitly exclude -symbols
+                    name.ends_with(&format!("-{}.zip", build_type)) && !name.contains("-symbols")
+                })
+            })
+        })
+        .ok_or_else(|| {
+            Error::not_found(format!("a {} package in the latest release", build_type))
+        })?;
+
+    let asset_name = asset["name"].as_str().unwrap_or_default().to_string();
+    let download_url = asset["browser_download_url"]
+        .as_str()
+        .ok_or_else(|| Error::not_found(format!("a download URL for {}", asset_name)))?
+        .to_string();
+
+    report!(
+        reporter,
+        "{}",
+        format!("Found stable release: {} ({})", asset_name, download_url).green()
+    );
+
+    let symbols_url = response["assets"].as_array().and_then(|assets| {
+        assets
+            .iter()
+            .find(|asset| {
+                asset["name"]
+                    .as_str()
+                    .is_some_and(|name| name.ends_with(&format!("-{}-symbols.zip", build_type)))
+            })
+            .and_then(|asset| asset["browser_download_url"].as_str())
+            .map(str::to_string)
+    });
+
+    Ok(Package {
+        name: asset_name,
+        download_url,
+        release_tag: response["tag_name"].as_str().map(str::to_string),
+        run_id: None,
+        artifact_id: None,
+        head_sha: None,
+        bridge_sha: None,
+        symbols_url,
+    })
+}
+
+pub fn fetch_x86_unified_artifact(
+    client: &dyn Http,
+    build_type: &str,
+    runs_filter: &str,
+    reporter: &mut dyn Reporter,
+) -> Result<Package> {
+    report!(
+        reporter,
+        "{}",
+        format!("Fetching unified x86 package ({} build)...", build_type).cyan()
+    );
+
+    let (mut package, run) =
+        fetch_run_artifact(client, Repo::Dxvk, runs_filter, "x86 unified", |name| {
+            catalog::get().artifacts.x86.matches(name, build_type)
+        })?;
+    package.bridge_sha = run["created_at"]
+        .as_str()
+        .and_then(|created_at| fetch_bridge_commit_at(client, created_at));
+    Ok(package)
+}
+
+pub fn fetch_x64_artifact(
+    client: &dyn Http,
+    build_type: &str,
+    runs_filter: &str,
+    reporter: &mut dyn Reporter,
+) -> Result<Package> {
+    report!(
+        reporter,
+        "{}",
+        format!("Fetching x64 package ({} build)...", build_type).cyan()
+    );
+
+    let (package, _) = fetch_run_artifact(client, Repo::Dxvk, runs_filter, "x64", |name| {
+        catalog::get().artifacts.x64.matches(name, build_type)
+    })?;
+    Ok(package)
+}
+
+/// Fetches a bridge-remix build, which holds the x86 bridge client and x64 server.
+pub fn fetch_bridge_artifact(
+    client: &dyn Http,
+    build_type: &str,
+    runs_filter: &str,
+    reporter: &mut dyn Reporter,
+) -> Result<Package> {
+    report!(
+        reporter,
+        "{}",
+        format!("Fetching bridge-remix package ({} build)...", build_type).cyan()
+    );
+
+    let (mut package, _) =
+        fetch_run_artifact(client, Repo::Bridge, runs_filter, "bridge-remix", |name| {
+            catalog::get().artifacts.bridge.matches(name, build_type)
+        })?;
+    // The run's commit is a bridge-remix one, not dxvk-remix
+    package.bridge_sha = package.head_sha.take();
+    Ok(package)
+}
+
+/// The commit a pull request currently points at.
+pub fn fetch_pr_head(client: &dyn Http, repo: Repo, number: u64) -> Result<String> {
+    let repo = catalog::get().repo(repo);
+    let pr_url = format!(
+        "{}/repos/{}/pulls/{}",
+        catalog::get().hosts.api,
+        repo,
+        number
+    );
+    let pr = http::get_json(client, &pr_url)?;
+    pr["head"]["sha"]
+        .as_str()
+        .map(str::to_string)
+        .ok_or_else(|| {
+            Error::not_found(format!("the head commit of {} PR #{}", repo, number)).into()
+        })
+}
+
+/// Finds an artifact in the latest successful workflow run matching `runs_filter`,
+/// such as `branch=main` or `head_sha=<commit>`, and returns it with the run. The
+/// symbols artifact is the one whose name matches once `-symbols` is taken out.
+pub fn fetch_run_artifact(
+    client: &dyn Http,
+    repo: Repo,
+    runs_filter: &str,
+    description: &str,
+    matches: impl Fn(&str) -> bool,
+) -> Result<(Package, Value)> {
+    let repo = catalog::get().repo(repo);
+    let runs_url = format!(
+        "{}/repos/{}/actions/runs?{}",
+        catalog::get().hosts.api,
+        repo,
+        runs_filter
+    );
+    let runs = http::get_json(client, &runs_url)?;
+
+    // Find the latest successful run
+    let latest_successful_run = runs["workflow_runs"]
+        .as_array()
+        .and_then(|runs_array| {
+            runs_array
+                .iter()
+                .filter(|run| run["conclusion"] == "success")
+                .max_by_key(|run| run["run_number"].as_u64().unwrap_or(0)) // Get the latest by run number
+        })
+        .ok_or_else(|| {
+            Error::not_found(format!("a successful {} run for {}", repo, runs_filter))
+        })?;
+
+    let artifacts_url = latest_successful_run["artifacts_url"]
+        .as_str()
+        .ok_or_else(|| {
+            Error::not_found(format!(
+                "the artifacts of {} run {}",
+                repo, latest_successful_run["run_number"]
+            ))
+        })?;
+
+    let artifacts = http::get_json(client, artifacts_url)?;
+
+    let (artifact_name, artifact_id) = artifacts["artifacts"]
+        .as_array()
+        .and_then(|artifacts_array| {
+            artifacts_array.iter().find_map(|a| {
+                let name = a["name"].as_str().filter(|name| matches(name))?;
+                Some((name.to_string(), a["id"].as_u64()?))
+            })
+        })
+        .ok_or_else(|| {
+            Error::not_found(format!(
+                "a {} artifact in {} run {}",
+                description, repo, latest_successful_run["run_number"]
+            ))
+        })?;
+
+    let download_url = format!(
+        "{}/{}/actions/artifacts/{}.zip",
+        catalog::get().hosts.nightly_link,
+        repo,
+        artifact_id
+    );
+
+    let symbols_url = artifacts["artifacts"]
+        .as_array()
+        .and_then(|artifacts_array| {
+            artifacts_array.iter().find(|a| {
+                a["name"].as_str().is_some_and(|name| {
+                    name.contains("-symbols") && matches(&name.replace("-symbols", ""))
+                })
+            })
+        })
+        .and_then(|a| a["id"].as_u64())
+        .map(|id| {
+            format!(
+                "{}/{}/actions/artifacts/{}.zip",
+                catalog::get().hosts.nightly_link,
+                repo,
+                id
+            )
+        });
+
+    let package = Package {
+        name: artifact_name,
+        download_url,
+        release_tag: None,
+        run_id: latest_successful_run["id"].as_u64(),
+        artifact_id: Some(artifact_id),
+        head_sha: latest_successful_run["head_sha"]
+            .as_str()
+            .map(str::to_string),
+        bridge_sha: None,
+        symbols_url,
+    };
+    Ok((package, latest_successful_run.clone()))
+}
+
+/// The bridge-remix commit that was current on main when a dxvk-remix run started,
+/// which is the bridge the unified package was built with.
+pub fn fetch_bridge_commit_at(client: &dyn Http, created_at: &str) -> Option<String> {
+    let commits_url = format!(
+        "{}/repos/{}/commits?sha=main&until={}&per_page=1",
+        catalog::get().hosts.api,
+        catalog::get().repo(Repo::Bridge),
+        created_at
+    );
+    let commits = http::get_json(client, &commits_url).ok()?;
+    commits[0]["sha"].as_str().map(str::to_string)
+}
@@ -0,0 +1,238 @@
+//! The HTTP layer. Everything the downloader fetches goes through an `Http` backend,
+//! reqwest's blocking client unless a caller brings its own, such as one serving
+//! recorded responses in tests.
+
+use std::collections::HashMap;
+use std::io::{self, Read, Seek, SeekFrom};
+
+use reqwest::blocking::Client;
+use reqwest::header::RANGE;
+use reqwest::StatusCode;
+use serde_json::Value;
+
+use crate::error::Error;
+
+/// What the downloader needs from an HTTP client
+pub trait Http {
+    /// Sends a GET request. Only failing to get a response is an error; error statuses
+    /// are returned like any other.
+    fn get(&self, url: &str) -> Result<Response, Error>;
+
+    /// Sends a HEAD request, to check that something exists without downloading it.
+    fn head(&self, url: &str) -> Result<Response, Error>;
+
+    /// Sends a GET request for `length` bytes of `url` starting at `start`. Backends and
+    /// servers without range support answer with the whole body and a 200 status.
+    fn get_range(&self, url: &str, _start: u64, _length: u64) -> Result<Response, Error> {
+        self.get(url)
+    }
+}
+
+pub struct Response {
+    pub status: StatusCode,
+    /// Header names are lowercase
+    pub headers: Vec<(String, String)>,
+    /// The body's size, when the server said
+    pub content_length: Option<u64>,
+    pub body: Box<dyn Read>,
+}
+
+impl Response {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+impl Http for Client {
+    fn get(&self, url: &str) -> Result<Response, Error> {
+        let response = self
+            .get(url)
+            .send()
+            .map_err(|e| Error::network(url, e.without_url()))?;
+        Ok(convert(response))
+    }
+
+    fn head(&self, url: &str) -> Result<Response, Error> {
+        let response = self
+            .head(url)
+            .send()
+            .map_err(|e| Error::network(url, e.without_url()))?;
+        Ok(convert(response))
+    }
+
+    fn get_range(&self, url: &str, start: u64, length: u64) -> Result<Response, Error> {
+        let response = self
+            .get(url)
+            .header(RANGE, format!("bytes={}-{}", start, start + length - 1))
+            .send()
+            .map_err(|e| Error::network(url, e.without_url()))?;
+        Ok(convert(response))
+    }
+}
+
+fn convert(response: reqwest::blocking::Response) -> Response {
+    Response {
+        status: response.status(),
+        headers: response
+            .headers()
+            .iter()
+            .filter_map(|(name, value)| {
+                Some((name.as_str().to_string(), value.to_str().ok()?.to_string()))
+            })
+            .collect(),
+        content_length: response.content_length(),
+        body: Box::new(response),
+    }
+}
+
+/// Sends a GET request, turning failures and error statuses into an `Error`.
+pub fn get(client: &dyn Http, url: &str) -> Result<Response, Error> {
+    check_status(url, client.get(url)?)
+}
+
+/// Fetches a whole body.
+pub fn get_bytes(client: &dyn Http, url: &str) -> Result<Vec<u8>, Error> {
+    let mut bytes = Vec::new();
+    get(client, url)?
+        .body
+        .read_to_end(&mut bytes)
+        .map_err(|e| Error::network(url, e))?;
+    Ok(bytes)
+}
+
+/// Fetches and parses a JSON document, usually from the GitHub API.
+pub fn get_json(client: &dyn Http, url: &str) -> Result<Value, Error> {
+    serde_json::from_slice(&get_bytes(client, url)?).map_err(|e| Error::network(url, e))
+}
+
+/// Fetches a text file.
+pub fn get_text(client: &dyn Http, url: &str) -> Result<String, Error> {
+    String::from_utf8(get_bytes(client, url)?).map_err(|e| Error::network(url, e))
+}
+
+/// Whether `url` exists, going by a HEAD request.
+pub fn exists(client: &dyn Http, url: &str) -> bool {
+    client
+        .head(url)
+        .is_ok_and(|response| response.status.is_success())
+}
+
+/// The size of `url` going by a HEAD request, when the server says.
+pub fn content_length(client: &dyn Http, url: &str) -> Option<u64> {
+    let response = client.head(url).ok()?;
+    // Read from the header, as a HEAD response has no body to take the length from
+    response
+        .status
+        .is_success()
+        .then(|| response.header("content-length")?.parse().ok())
+        .flatten()
+}
+
+/// Bytes fetched per range request
+const BLOCK_SIZE: u64 = 64 * 1024;
+
+/// A file on a server read through range requests, so the list of files at the end of a
+/// zip can be read without downloading the rest. Blocks are kept once fetched.
+pub struct RemoteFile<'a> {
+    client: &'a dyn Http,
+    url: String,
+    size: u64,
+    position: u64,
+    blocks: HashMap<u64, Vec<u8>>,
+}
+
+impl<'a> RemoteFile<'a> {
+    /// `size` is the file's full size, usually from `content_length`.
+    pub fn new(client: &'a dyn Http, url: &str, size: u64) -> Self {
+        Self {
+            client,
+            url: url.to_string(),
+            size,
+            position: 0,
+            blocks: HashMap::new(),
+        }
+    }
+
+    fn block(&mut self, index: u64) -> io::Result<&[u8]> {
+        if !self.blocks.contains_key(&index) {
+            let start = index * BLOCK_SIZE;
+            let length = BLOCK_SIZE.min(self.size - start);
+            let response =
+                get_range(self.client, &self.url, start, length).map_err(io::Error::other)?;
+            if response.status != StatusCode::PARTIAL_CONTENT {
+                return Err(io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    format!("{} doesn't support range requests", self.url),
+                ));
+            }
+            let mut data = Vec::with_capacity(length as usize);
+            response.body.take(length).read_to_end(&mut data)?;
+            self.blocks.insert(index, data);
+        }
+        Ok(&self.blocks[&index])
+    }
+}
+
+impl Read for RemoteFile<'_> {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        if self.position >= self.size || buffer.is_empty() {
+            return Ok(0);
+        }
+        let offset = (self.position % BLOCK_SIZE) as usize;
+        let block = self.block(self.position / BLOCK_SIZE)?;
+        let count = buffer.len().min(block.len().saturating_sub(offset));
+        buffer[..count].copy_from_slice(&block[offset..offset + count]);
+        self.position += count as u64;
+        Ok(count)
+    }
+}
+
+impl Seek for RemoteFile<'_> {
+    fn seek(&mut self, position: SeekFrom) -> io::Result<u64> {
+        let position = match position {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(offset) => self.size.checked_add_signed(offset),
+            SeekFrom::Current(offset) => self.position.checked_add_signed(offset),
+        };
+        self.position = position.ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "seek before the start of the file",
+            )
+        })?;
+        Ok(self.position)
+    }
+}
+
+/// Sends a range request, turning failures and error statuses into an `Error`.
+fn get_range(client: &dyn Http, url: &str, start: u64, length: u64) -> Result<Response, Error> {
+    check_status(url, client.get_range(url, start, length)?)
+}
+
+fn check_status(url: &str, response: Response) -> Result<Response, Error> {
+    let status = response.status;
+    if status.is_success() {
+        return Ok(response);
+    }
+    let rate_limited = status == StatusCode::TOO_MANY_REQUESTS
+        || (status == StatusCode::FORBIDDEN
+            && response.header("x-ratelimit-remaining") == Some("0"));
+    Err(if rate_limited {
+        Error::RateLimited {
+            url: url.to_string(),
+            reset: response
+                .header("x-ratelimit-reset")
+                .and_then(|reset| reset.parse().ok()),
+        }
+    } else if status == StatusCode::NOT_FOUND {
+        Error::not_found(url)
+    } else {
+        Error::HttpStatus {
+            url: url.to_string(),
+            status,
+        }
+    })
+}
@@ -0,0 +1,861 @@
+//! The install flow: resolving the build the options ask for, downloading and laying it
+//! out in the `remix` folder, and deploying it into a game folder.
+
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use colored::*;
+use serde::Serialize;
+
+use crate::catalog::Repo;
+use crate::download::{self, Downloader};
+use crate::error::Error;
+use crate::github::{self, Package};
+use crate::http::Http;
+use crate::layout;
+use crate::lockfile::{DownloadLog, Lockfile};
+use crate::manifest::{self, BuildSource};
+use crate::progress::{Phase, Reporter};
+use crate::{config, conflicts, deploy, files, game, notes, pe};
+
+/// What to install and where
+pub struct InstallOptions {
+    pub runtime: Origin,
+    /// Where the bridge comes from when it differs from the runtime
+    pub bridge: Option<Origin>,
+    pub is_x86: bool,
+    pub build_type: String,
+    pub game_dir: Option<PathBuf>,
+    /// Whether to add the dxwrapper layer that lets D3D8 games run on Remix
+    pub dx8_wrapper: bool,
+    pub conflicts: ConflictAction,
+    /// Keep PDBs and add the build's symbols package
+    pub with_symbols: bool,
+    /// Install this exact build instead of resolving the latest one
+    pub locked: Option<Lockfile>,
+    /// Where to record the lockfile for this install
+    pub write_lock: Option<PathBuf>,
+    /// Ask before downloading, once the build's notes are shown
+    pub confirm: bool,
+    /// Asks the user a yes or no question, given the answer to suggest, and returns their
+    /// answer. Without it nothing is asked: installs go ahead and conflicts are reported.
+    pub ask: Option<Box<Ask>>,
+    /// Where the build is downloaded and assembled, `remix` in the working folder for the
+    /// command line program
+    pub remix_dir: PathBuf,
+    /// Layout rules to use instead of the catalog's or the built-in ones
+    pub layout_file: Option<PathBuf>,
+}
+
+/// A yes or no question for the user, see `InstallOptions::ask`
+pub type Ask = dyn Fn(&str, bool) -> bool;
+
+/// Where a component of the install comes from
+#[derive(Clone, PartialEq, Eq)]
+pub enum Origin {
+    Stable,
+    Dev,
+    /// The latest successful run of a pull request
+    Pr(u64),
+    /// A package zip on disk
+    Local(PathBuf),
+}
+
+pub fn parse_origin(value: &str) -> Result<Origin, String> {
+    match value {
+        "stable" => Ok(Origin::Stable),
+        "dev" => Ok(Origin::Dev),
+        _ => {
+            if let Some(number) = value.strip_prefix("pr:") {
+                return number
+                    .parse()
+                    .map(Origin::Pr)
+                    .map_err(|_| format!("\"{}\" is not a pull request number", number));
+            }
+            let path = PathBuf::from(value);
+            if path.extension().is_some_and(|ext| ext == "zip") && path.is_file() {
+                // Recorded in the manifest, so `repair` finds it from any folder
+                path.canonicalize()
+                    .map(Origin::Local)
+                    .map_err(|e| format!("could not read {}: {}", value, e))
+            } else {
+                Err("expected stable, dev, pr:<number> or the path of a .zip".to_string())
+            }
+        }
+    }
+}
+
+/// The parts of Remix that can come from different builds
+#[derive(Clone, Copy)]
+enum Component {
+    Runtime,
+    Bridge,
+}
+
+/// What to do with other graphics wrappers found in the game folder
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ConflictAction {
+    Report,
+    MoveAside,
+    Ask,
+}
+
+/// Reads the machine type from a game executable, returning whether it is 32-bit.
+pub fn detect_game_arch(game_exe: &Path, reporter: &mut dyn Reporter) -> Option<bool> {
+    match pe::PeFile::open(game_exe).map(|pe| pe.machine()) {
+        Ok(machine @ (pe::Machine::X86 | pe::Machine::X64)) => {
+            report!(
+                reporter,
+                "{}",
+                format!("Detected a {} game", machine.describe()).green()
+            );
+            Some(machine == pe::Machine::X86)
+        }
+        Ok(machine) => {
+            reporter.warning(&format!(
+                "{} uses an {}",
+                display_path(game_exe),
+                machine.describe()
+            ));
+            None
+        }
+        Err(e) => {
+            reporter.warning(&format!("Could not detect the game type: {:#}", e));
+            None
+        }
+    }
+}
+
+/// Checks which Direct3D version the game uses, returning whether it needs the
+/// dxwrapper D3D8 layer. Without a game to inspect, x86 installs keep the layer.
+pub fn detect_dx8_wrapper(
+    game_exe: Option<&Path>,
+    is_x86: bool,
+    reporter: &mut dyn Reporter,
+) -> bool {
+    let Some(game_exe) = game_exe else {
+        return is_x86;
+    };
+    let apis = match game::detect_graphics_apis(game_exe) {
+        Ok(apis) => apis,
+        Err(e) => {
+            reporter.warning(&format!(
+                "Could not detect the game's graphics API: {:#}",
+                e
+            ));
+            return is_x86;
+        }
+    };
+    if apis.is_empty() {
+        reporter.warning("Could not find any Direct3D imports in the game, keeping the D3D8 layer");
+        return is_x86;
+    }
+
+    let names: Vec<&str> = apis.iter().map(|api| api.name()).collect();
+    report!(
+        reporter,
+        "{}",
+        format!("Detected graphics API: {}", names.join(", ")).green()
+    );
+    if !apis.iter().any(|api| api.is_supported()) {
+        reporter.warning(&format!(
+            "RTX Remix only supports Direct3D 8 and 9 games, this game uses {}",
+            names.join(", ")
+        ));
+    }
+
+    let is_d3d8 = apis.contains(&game::GraphicsApi::D3D8);
+    if is_d3d8 && !is_x86 {
+        reporter.warning("Direct3D 8 games are only supported by the x86 build");
+    } else if is_x86 && !is_d3d8 {
+        report!(
+            reporter,
+            "{}",
+            "Skipping the dxwrapper D3D8 layer, it is only needed for Direct3D 8 games".cyan()
+        );
+    }
+    is_x86 && is_d3d8
+}
+
+/// What an install put where
+pub struct Installed {
+    /// The game folder, or the `remix` folder without one
+    pub path: PathBuf,
+    pub source: BuildSource,
+    /// Files of the build, relative to `path`
+    pub files: Vec<String>,
+}
+
+/// Downloads a build through `client` and deploys it, reporting progress to `reporter`.
+pub fn install(
+    options: &InstallOptions,
+    client: &dyn Http,
+    reporter: &mut dyn Reporter,
+) -> Result<Installed> {
+    // Remember what the install being replaced documented, to show what's new
+    let previous_path = options.game_dir.as_deref().unwrap_or(&options.remix_dir);
+    let previous_options = config::read_default(previous_path, "dxvk.conf");
+
+    let (source, final_path) = download_build(
+        options,
+        installed_source(previous_path).as_ref(),
+        client,
+        reporter,
+    )?;
+
+    report!(reporter, "{}", "Download complete!".green().bold());
+    report!(reporter, "You can find the latest RTX Remix install in:");
+    report!(reporter, "{}", clickable_path(&final_path));
+
+    if let Some(game_dir) = &options.game_dir {
+        // Only once the build is ready, so nothing is moved for an install that stops early
+        let set_aside = check_conflicts(
+            game_dir,
+            options.conflicts,
+            options.ask.as_deref(),
+            reporter,
+        )?;
+        deploy::deploy_to_game_dir(&final_path, game_dir, &set_aside, reporter)?;
+    }
+
+    if let (Some(previous), Some(current)) = (
+        previous_options,
+        config::read_default(&final_path, "dxvk.conf"),
+    ) {
+        config::print_option_diff(
+            "dxvk.conf",
+            &config::diff_options(&previous, &current),
+            reporter,
+        );
+    }
+
+    report!(reporter, "{}", "RTX Remix install guide:".yellow());
+    report!(
+        reporter,
+        "{}",
+        "https://github.com/NVIDIAGameWorks/rtx-remix/wiki/runtime-user-guide".cyan()
+    );
+
+    let installed = Installed {
+        path: options.game_dir.clone().unwrap_or(final_path.clone()),
+        source,
+        files: files::list_files(&final_path)
+            .map_err(|e| Error::filesystem(&final_path, e))?
+            .iter()
+            .map(|relative| files::path_key(relative))
+            .collect(),
+    };
+    reporter.installed(&installed);
+    Ok(installed)
+}
+
+/// Reports wrappers in the game folder that conflict with Remix, returning the files to
+/// move aside when deploying. Without `ask`, `ConflictAction::Ask` only reports them.
+pub fn check_conflicts(
+    game_dir: &Path,
+    action: ConflictAction,
+    ask: Option<&Ask>,
+    reporter: &mut dyn Reporter,
+) -> Result<Vec<String>> {
+    if !game_dir.is_dir() {
+        return Ok(Vec::new());
+    }
+    let found = conflicts::scan(game_dir)?;
+    if found.is_empty() {
+        return Ok(Vec::new());
+    }
+    conflicts::print_conflicts(&found, reporter);
+
+    let move_aside = match action {
+        ConflictAction::MoveAside => true,
+        ConflictAction::Report => false,
+        ConflictAction::Ask => ask.is_some_and(|ask| {
+            ask(
+                &format!(
+                    "Move them into {} so Remix can load? They are restored on uninstall.",
+                    deploy::BACKUP_DIR
+                ),
+                false,
+            )
+        }),
+    };
+
+    if !move_aside {
+        if action == ConflictAction::Report {
+            reporter.warning("Run with --move-conflicts to move them aside");
+        }
+        return Ok(Vec::new());
+    }
+    Ok(found
+        .into_iter()
+        .map(|conflict| conflict.file_name)
+        .collect())
+}
+
+/// The build currently installed in a game or remix folder, if the downloader put it there.
+pub fn installed_source(install_path: &Path) -> Option<BuildSource> {
+    manifest::Manifest::load(install_path)
+        .ok()
+        .map(|manifest| manifest.source)
+}
+
+/// The latest build for some options next to the one installed where they point
+#[derive(Serialize)]
+pub struct Update {
+    /// Whether the latest build differs from the installed one, or nothing is installed
+    pub available: bool,
+    pub installed: Option<BuildSource>,
+    pub latest: BuildSource,
+}
+
+/// Resolves the latest build for the options and compares it with the installed one.
+pub fn check_for_update(
+    client: &dyn Http,
+    options: &InstallOptions,
+    reporter: &mut dyn Reporter,
+) -> Result<Update> {
+    let installed = installed_source(options.game_dir.as_deref().unwrap_or(&options.remix_dir));
+    let latest = resolve_build(client, options, reporter)?;
+    Ok(Update {
+        available: installed
+            .as_ref()
+            .is_none_or(|installed| !same_build(installed, &latest)),
+        installed,
+        latest,
+    })
+}
+
+/// Whether two builds were made from the same packages.
+pub fn same_build(a: &BuildSource, b: &BuildSource) -> bool {
+    a.download_url == b.download_url
+        && a.bridge.as_ref().map(|bridge| &bridge.download_url)
+            == b.bridge.as_ref().map(|bridge| &bridge.download_url)
+}
+
+/// Lays the build out in a temporary folder without downloading it, from the sizes the
+/// hosts report and the file lists of the zips read remotely, and shows everything the
+/// install would download and change, leaving the game and remix folders alone.
+pub fn print_install_plan(
+    options: &InstallOptions,
+    client: &dyn Http,
+    reporter: &mut dyn Reporter,
+) -> Result<()> {
+    let remix_path = options.remix_dir.as_path();
+    let previous_path = options.game_dir.as_deref().unwrap_or(remix_path);
+    let (source, log) = resolve_source(
+        client,
+        options,
+        installed_source(previous_path).as_ref(),
+        reporter,
+    )?;
+
+    let staging = std::env::temp_dir().join(format!("remix-dry-run-{}", std::process::id()));
+    cleanup_existing_directory(&staging, reporter)?;
+    let rules = layout::Rules::load(options.layout_file.as_deref(), reporter)?;
+    let mut downloader = Downloader::new(client, log, reporter);
+    downloader.dry_run = true;
+    let result = assemble_build(&mut downloader, &source, &staging, &rules).and_then(|_| {
+        report!(
+            downloader.reporter,
+            "\n{}",
+            "Dry run, nothing was installed".green().bold()
+        );
+        for line in build_names(&source) {
+            report!(downloader.reporter, "  {}", line);
+        }
+        let summary = downloader.plan.report(
+            &staging,
+            remix_path,
+            options.game_dir.as_deref(),
+            downloader.reporter,
+        )?;
+        downloader.reporter.planned(&summary);
+        Ok(())
+    });
+    fs::remove_dir_all(&staging).ok();
+    result
+}
+
+/// The build to install: the locked one, or the latest matching the options, once its
+/// notes have been shown.
+pub fn resolve_source(
+    client: &dyn Http,
+    options: &InstallOptions,
+    previous: Option<&BuildSource>,
+    reporter: &mut dyn Reporter,
+) -> Result<(BuildSource, DownloadLog)> {
+    Ok(match &options.locked {
+        Some(lockfile) => (lockfile.source.clone(), DownloadLog::pinned(lockfile)),
+        None => {
+            let source = resolve_build(client, options, reporter)?;
+            reporter.resolved(&source);
+            notes::show(client, &source, previous, reporter);
+            let declined = |ask: &Ask| !ask("Download and install this build?", true);
+            if options.confirm && options.ask.as_deref().is_some_and(declined) {
+                anyhow::bail!(Error::invalid("Install cancelled"));
+            }
+            (source, DownloadLog::default())
+        }
+    })
+}
+
+/// Downloads and assembles the selected build in the remix folder, returning it and its path.
+pub fn download_build(
+    options: &InstallOptions,
+    previous: Option<&BuildSource>,
+    client: &dyn Http,
+    reporter: &mut dyn Reporter,
+) -> Result<(BuildSource, PathBuf)> {
+    let (source, log) = resolve_source(client, options, previous, reporter)?;
+    let rules = layout::Rules::load(options.layout_file.as_deref(), reporter)?;
+
+    // Create and clean the remix folder
+    let remix_path = &options.remix_dir;
+    // Hold on to edited configs from the previous download so they can be merged in
+    let saved_configs = config::capture_configs(remix_path);
+    cleanup_existing_directory(remix_path, reporter)?;
+    let final_path = remix_path
+        .canonicalize()
+        .map_err(|e| Error::filesystem(remix_path, e))?;
+
+    let mut downloader = Downloader::new(client, log, reporter);
+    assemble_build(&mut downloader, &source, &final_path, &rules)?;
+    manifest::write_manifest(&final_path, &source)?;
+
+    let lockfile = downloader.log.finish(&source)?;
+    if let Some(lock_path) = &options.write_lock {
+        lockfile.save(lock_path)?;
+        report!(
+            reporter,
+            "{}",
+            format!(
+                "Wrote lockfile with {} files to {}",
+                lockfile.files.len(),
+                display_path(lock_path)
+            )
+            .green()
+        );
+    }
+
+    for (path, text) in config::merge_saved(&saved_configs, &final_path, reporter) {
+        let config_path = final_path.join(path);
+        // Skip configs for folders this build doesn't have, e.g. .trex on x64
+        if config_path.parent().is_some_and(Path::exists) {
+            fs::write(&config_path, text).map_err(|e| Error::filesystem(&config_path, e))?;
+        }
+    }
+    Ok((source, final_path))
+}
+
+/// Picks the packages to download for the selected components, architecture and build type.
+pub fn resolve_build(
+    client: &dyn Http,
+    options: &InstallOptions,
+    reporter: &mut dyn Reporter,
+) -> Result<BuildSource> {
+    if options.bridge.is_some() && !options.is_x86 {
+        anyhow::bail!(Error::invalid(
+            "64-bit games don't use the bridge, leave out --bridge"
+        ));
+    }
+    let mut source = resolve_component(
+        client,
+        options,
+        &options.runtime,
+        Component::Runtime,
+        reporter,
+    )?;
+
+    if let Some(bridge) = &options.bridge {
+        // The same release or zip already has its own bridge; dev and PR bridges come
+        // from bridge-remix rather than the runtime's repo
+        let same_package =
+            *bridge == options.runtime && matches!(bridge, Origin::Stable | Origin::Local(_));
+        if !same_package {
+            let bridge_source =
+                resolve_component(client, options, bridge, Component::Bridge, reporter)?;
+            source.bridge = Some(Box::new(bridge_source));
+        }
+    }
+    Ok(source)
+}
+
+fn resolve_component(
+    client: &dyn Http,
+    options: &InstallOptions,
+    origin: &Origin,
+    component: Component,
+    reporter: &mut dyn Reporter,
+) -> Result<BuildSource> {
+    let build_type = options.build_type.as_str();
+    let package = match (origin, component) {
+        (Origin::Stable, _) => {
+            report!(
+                reporter,
+                "{}",
+                format!("\nDownloading stable {} build...", build_type).cyan()
+            );
+            github::fetch_latest_stable_release(client, build_type, reporter)?
+        }
+        (Origin::Dev, Component::Bridge) => {
+            github::fetch_bridge_artifact(client, build_type, "branch=main", reporter)?
+        }
+        (Origin::Dev, Component::Runtime) if options.is_x86 => {
+            github::fetch_x86_unified_artifact(client, build_type, "branch=main", reporter)?
+        }
+        (Origin::Dev, Component::Runtime) => {
+            github::fetch_x64_artifact(client, build_type, "branch=main", reporter)?
+        }
+        (Origin::Pr(number), Component::Bridge) => {
+            report!(
+                reporter,
+                "{}",
+                format!("Fetching bridge-remix PR #{}...", number).cyan()
+            );
+            let runs_filter = format!(
+                "head_sha={}",
+                github::fetch_pr_head(client, Repo::Bridge, *number)?
+            );
+            github::fetch_bridge_artifact(client, build_type, &runs_filter, reporter)?
+        }
+        (Origin::Pr(number), Component::Runtime) => {
+            let runs_filter = format!(
+                "head_sha={}",
+                github::fetch_pr_head(client, Repo::Dxvk, *number)?
+            );
+            if options.is_x86 {
+                github::fetch_x86_unified_artifact(client, build_type, &runs_filter, reporter)?
+            } else {
+                github::fetch_x64_artifact(client, build_type, &runs_filter, reporter)?
+            }
+        }
+        (Origin::Local(path), _) => Package {
+            name: path
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string(),
+            download_url: display_path(path),
+            release_tag: None,
+            run_id: None,
+            artifact_id: None,
+            head_sha: None,
+            bridge_sha: None,
+            symbols_url: None,
+        },
+    };
+
+    if options.with_symbols && package.symbols_url.is_none() {
+        reporter.warning(&format!("No debug symbols found for {}", package.name));
+    }
+    let symbols_url = package.symbols_url.filter(|_| options.with_symbols);
+
+    let is_stable = match origin {
+        Origin::Stable => true,
+        Origin::Local(path) => is_release_package(path, reporter)?,
+        _ => false,
+    };
+
+    Ok(BuildSource {
+        is_stable,
+        is_x86: options.is_x86,
+        build_type: options.build_type.clone(),
+        dx8_wrapper: options.is_x86 && options.dx8_wrapper,
+        package_name: package.name,
+        download_url: package.download_url,
+        release_tag: package.release_tag,
+        run_id: package.run_id,
+        artifact_id: package.artifact_id,
+        head_sha: package.head_sha,
+        bridge_sha: package.bridge_sha,
+        symbols_url,
+        pull_request: match origin {
+            Origin::Pr(number) => Some(*number),
+            _ => None,
+        },
+        local_zip: match origin {
+            Origin::Local(path) => Some(path.clone()),
+            _ => None,
+        },
+        bridge: None,
+    })
+}
+
+/// Whether a local zip is a stable release rather than a workflow artifact. Releases ship
+/// d3d8to9.dll at the top level, which the stable layout rules take out again.
+fn is_release_package(path: &Path, reporter: &mut dyn Reporter) -> Result<bool> {
+    let is_release = files::open_zip(path)?
+        .file_names()
+        .any(|name| name.eq_ignore_ascii_case("d3d8to9.dll"));
+    if is_release {
+        report!(
+            reporter,
+            "{}",
+            format!("{} is a stable release package", display_path(path)).cyan()
+        );
+    }
+    Ok(is_release)
+}
+
+/// Downloads a resolved package into `final_path` and lays it out for its architecture.
+pub fn assemble_build(
+    downloader: &mut Downloader,
+    source: &BuildSource,
+    final_path: &Path,
+    rules: &layout::Rules,
+) -> Result<()> {
+    let is_x86 = source.is_x86;
+    downloader.reporter.start(phases(source).len());
+
+    if source.is_stable {
+        let stable_zip = final_path.join("stable-release.zip");
+
+        downloader.phase(Phase::Download);
+        report!(
+            downloader.reporter,
+            "Downloading stable release from GitHub..."
+        );
+        download::fetch_package(downloader, source, &stable_zip)?;
+
+        downloader.phase(Phase::Extract);
+        report!(downloader.reporter, "Extracting stable release...");
+        download::extract_package(downloader, &source.package_name, &stable_zip, final_path)?;
+
+        // Cleanup zip file
+        fs::remove_file(&stable_zip).map_err(|e| Error::filesystem(&stable_zip, e))?;
+
+        // Drop, rename and move files as the layout rules say
+        downloader.phase(Phase::Layout);
+        rules.apply(layout::Stage::Package, source, final_path, &mut |change| {
+            downloader.change(change)
+        })?;
+
+        // Swap in the bridge when it comes from a different package
+        if let Some(bridge) = &source.bridge {
+            downloader.phase(Phase::Bridge);
+            download::overlay_bridge(downloader, bridge, final_path)?;
+        }
+
+        // Write build info with actual package name
+        write_build_names(final_path, &build_names(source), downloader.reporter)?;
+
+        if is_x86 {
+            // Download and extract dx8 binaries for x86 D3D8 games
+            if source.dx8_wrapper {
+                downloader.phase(Phase::Dx8Wrapper);
+                download::download_and_extract_dx8_binaries(downloader, rules, source, final_path)?;
+            }
+            // Download all additional files and licenses
+            downloader.phase(Phase::Configs);
+            download::download_additional_files(downloader, source, final_path)?;
+            downloader.phase(Phase::Licenses);
+            download::download_licenses(downloader, source, final_path)?;
+        } else {
+            // Download only DXVK-related licenses
+            downloader.phase(Phase::Licenses);
+            download::download_licenses(downloader, source, final_path)?;
+        }
+    } else if is_x86 {
+        let artifact_name = source.package_name.clone();
+        let unified_zip = final_path.join("rtx-remix-x86.zip");
+
+        downloader.phase(Phase::Download);
+        report!(
+            downloader.reporter,
+            "Downloading unified x86 package: {}",
+            artifact_name
+        );
+        download::fetch_package(downloader, source, &unified_zip)?;
+
+        downloader.phase(Phase::Extract);
+        report!(downloader.reporter, "Extracting unified package...");
+        download::extract_package(downloader, &source.package_name, &unified_zip, final_path)?;
+
+        // Cleanup zip file
+        fs::remove_file(&unified_zip).map_err(|e| Error::filesystem(&unified_zip, e))?;
+
+        // Drop, rename and move files as the layout rules say
+        downloader.phase(Phase::Layout);
+        rules.apply(layout::Stage::Package, source, final_path, &mut |change| {
+            downloader.change(change)
+        })?;
+
+        // Swap in the bridge when it comes from a different package
+        if let Some(bridge) = &source.bridge {
+            downloader.phase(Phase::Bridge);
+            download::overlay_bridge(downloader, bridge, final_path)?;
+        }
+
+        // Download and extract dx8 binaries for x86 D3D8 games
+        if source.dx8_wrapper {
+            downloader.phase(Phase::Dx8Wrapper);
+            download::download_and_extract_dx8_binaries(downloader, rules, source, final_path)?;
+        }
+
+        // Download additional files and licenses
+        downloader.phase(Phase::Configs);
+        download::download_additional_files(downloader, source, final_path)?;
+        downloader.phase(Phase::Licenses);
+        download::download_licenses(downloader, source, final_path)?;
+
+        // Write build info
+        write_build_names(final_path, &build_names(source), downloader.reporter)?;
+    } else {
+        let artifact_name = source.package_name.clone();
+        let x64_zip = final_path.join("rtx-remix-x64.zip");
+
+        downloader.phase(Phase::Download);
+        report!(
+            downloader.reporter,
+            "Downloading x64 package: {}",
+            artifact_name
+        );
+        download::fetch_package(downloader, source, &x64_zip)?;
+
+        downloader.phase(Phase::Extract);
+        report!(downloader.reporter, "Extracting x64 package...");
+        download::extract_package(downloader, &source.package_name, &x64_zip, final_path)?;
+
+        // Cleanup zip file
+        fs::remove_file(&x64_zip).map_err(|e| Error::filesystem(&x64_zip, e))?;
+
+        // Drop, rename and move files as the layout rules say
+        downloader.phase(Phase::Layout);
+        rules.apply(layout::Stage::Package, source, final_path, &mut |change| {
+            downloader.change(change)
+        })?;
+
+        // For x64, only download DXVK-related licenses
+        downloader.phase(Phase::Licenses);
+        download::download_licenses(downloader, source, final_path)?;
+
+        // Write build info
+        write_build_names(final_path, &build_names(source), downloader.reporter)?;
+    }
+
+    if let Some(symbols_url) = &source.symbols_url {
+        downloader.phase(Phase::Symbols);
+        download::download_symbols(downloader, symbols_url, final_path)?;
+    }
+
+    // Keep the upstream configs around as the base for merging future updates
+    config::store_defaults(final_path)?;
+    downloader.reporter.finish();
+    Ok(())
+}
+
+/// The phases assembling `source` goes through, in order.
+pub fn phases(source: &BuildSource) -> Vec<Phase> {
+    let mut phases = vec![Phase::Download, Phase::Extract, Phase::Layout];
+    if source.is_x86 {
+        if source.bridge.is_some() {
+            phases.push(Phase::Bridge);
+        }
+        if source.dx8_wrapper {
+            phases.push(Phase::Dx8Wrapper);
+        }
+        phases.push(Phase::Configs);
+    }
+    phases.push(Phase::Licenses);
+    if source.symbols_url.is_some() {
+        phases.push(Phase::Symbols);
+    }
+    phases
+}
+
+// === File System Operations ===
+pub fn cleanup_existing_directory(path: &Path, reporter: &mut dyn Reporter) -> Result<()> {
+    if path.exists() {
+        report!(
+            reporter,
+            "{}",
+            "Cleaning up existing installation...".cyan()
+        );
+        fs::remove_dir_all(path).map_err(|e| Error::filesystem(path, e))?;
+    }
+    fs::create_dir_all(path).map_err(|e| Error::filesystem(path, e))?;
+    Ok(())
+}
+
+// === Utility Functions ===
+
+/// Lines for build-names.txt, noting where each component came from when they differ
+/// or weren't the latest build of a stream.
+pub fn build_names(source: &BuildSource) -> Vec<String> {
+    match &source.bridge {
+        Some(bridge) => vec![
+            format!(
+                "runtime: {} ({})",
+                source.package_name,
+                describe_origin(source)
+            ),
+            format!(
+                "bridge: {} ({})",
+                bridge.package_name,
+                describe_origin(bridge)
+            ),
+        ],
+        None if source.pull_request.is_some() || source.local_zip.is_some() => {
+            vec![format!(
+                "{} ({})",
+                source.package_name,
+                describe_origin(source)
+            )]
+        }
+        None => vec![source.package_name.clone()],
+    }
+}
+
+fn describe_origin(source: &BuildSource) -> String {
+    if let Some(local_zip) = &source.local_zip {
+        format!("local zip {}", display_path(local_zip))
+    } else if let Some(number) = source.pull_request {
+        format!("PR #{} run {}", number, source.run_id.unwrap_or_default())
+    } else if source.is_stable {
+        format!(
+            "stable release {}",
+            source.release_tag.as_deref().unwrap_or("unknown")
+        )
+    } else {
+        format!("dev run {}", source.run_id.unwrap_or_default())
+    }
+}
+
+fn write_build_names(
+    final_path: &Path,
+    build_names: &[String],
+    reporter: &mut dyn Reporter,
+) -> Result<()> {
+    let build_names_path = final_path.join("build-names.txt");
+    let mut file =
+        fs::File::create(&build_names_path).map_err(|e| Error::filesystem(&build_names_path, e))?;
+    for name in build_names {
+        writeln!(file, "{}", name).map_err(|e| Error::filesystem(&build_names_path, e))?;
+    }
+    report!(
+        reporter,
+        "{}",
+        format!(
+            "Created build-names.txt with {} build names",
+            build_names.len()
+        )
+        .green()
+    );
+    Ok(())
+}
+
+pub fn display_path(path: &Path) -> String {
+    path.to_string_lossy().replace(r"\\?\", "")
+}
+
+pub fn clickable_path(path: &Path) -> String {
+    let clean_path = display_path(path);
+    format!(
+        "\x1B]8;;file://{}\x07{}\x1B]8;;\x07",
+        clean_path, clean_path
+    )
+    .cyan()
+    .to_string()
+}
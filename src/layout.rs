@@ -0,0 +1,247 @@
+//! Declarative rules for turning extracted packages into the install layout, so upstream
+//! packaging changes can be handled by editing `remix-layout.toml` instead of the code.
+
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use colored::*;
+use serde::Deserialize;
+
+use crate::catalog;
+use crate::error::Error;
+use crate::files::{list_files, path_key};
+use crate::manifest::BuildSource;
+use crate::profiles::Stream;
+use crate::progress::Reporter;
+use crate::Arch;
+
+pub const LAYOUT_FILE: &str = "remix-layout.toml";
+pub const DEFAULT_RULES: &str = include_str!("layout.toml");
+
+#[derive(Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Rules {
+    #[serde(rename = "rule", default)]
+    rules: Vec<Rule>,
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Rule {
+    stage: Stage,
+    action: Action,
+    path: String,
+    to: Option<String>,
+    arch: Option<Arch>,
+    stream: Option<Stream>,
+    /// Only applies when symbols were (or weren't) requested
+    symbols: Option<bool>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Stage {
+    /// Right after the build package is extracted
+    Package,
+    /// After the dxwrapper D3D8 binaries are extracted
+    Dx8,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Action {
+    Remove,
+    Keep,
+    Rename,
+    Move,
+    Require,
+}
+
+impl Rules {
+    /// Reads the rules in `layout_file`, or without one the catalog's rules or the
+    /// built-in ones.
+    pub fn load(layout_file: Option<&Path>, reporter: &mut dyn Reporter) -> Result<Self> {
+        let Some(path) = layout_file else {
+            if let Some(rules) = &catalog::get().layout {
+                return Ok(rules.clone());
+            }
+            return Self::parse(DEFAULT_RULES).context("Built-in layout rules are invalid");
+        };
+        report!(
+            reporter,
+            "{}",
+            format!("Using layout rules from {}", path.display()).yellow()
+        );
+        let text = fs::read_to_string(path).map_err(|e| Error::filesystem(path, e))?;
+        Self::parse(&text).map_err(|e| {
+            Error::invalid(format!("Could not parse {}: {:#}", path.display(), e)).into()
+        })
+    }
+
+    fn parse(text: &str) -> Result<Self> {
+        let rules: Self = toml::from_str(text)?;
+        rules.validate()?;
+        Ok(rules)
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        for rule in &self.rules {
+            let needs_to = matches!(rule.action, Action::Rename | Action::Move);
+            if needs_to != rule.to.is_some() {
+                anyhow::bail!(
+                    "Rule for {} {} a `to`",
+                    rule.path,
+                    if needs_to { "needs" } else { "can't have" }
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Runs the rules for a stage against the files in `final_path`, passing each change to `changed`.
+    pub fn apply(
+        &self,
+        stage: Stage,
+        source: &BuildSource,
+        final_path: &Path,
+        changed: &mut dyn FnMut(String),
+    ) -> Result<()> {
+        let mut kept = Vec::new();
+        for rule in self.rules.iter().filter(|rule| rule.applies(stage, source)) {
+            match rule.action {
+                Action::Keep => kept.push(rule.path.as_str()),
+                Action::Remove => {
+                    for key in matching(final_path, &rule.path)? {
+                        if kept.iter().any(|pattern| glob_match(pattern, &key)) {
+                            continue;
+                        }
+                        let path = final_path.join(&key);
+                        fs::remove_file(&path).map_err(|e| Error::filesystem(&path, e))?;
+                        changed(format!("remove {}", key));
+                    }
+                }
+                Action::Rename => {
+                    let from = final_path.join(&rule.path);
+                    if from.is_file() {
+                        let to = from.with_file_name(rule.to.as_deref().unwrap_or_default());
+                        fs::rename(&from, &to).map_err(|e| Error::filesystem(&from, e))?;
+                        changed(format!(
+                            "rename {} to {}",
+                            rule.path,
+                            rule.to.as_deref().unwrap_or_default()
+                        ));
+                    }
+                }
+                Action::Move => {
+                    let from = final_path.join(&rule.path);
+                    if from.is_dir() {
+                        let to = final_path.join(rule.to.as_deref().unwrap_or_default());
+                        let to_prefix = match rule.to.as_deref() {
+                            Some("") | None => String::new(),
+                            Some(to) => format!("{}/", to),
+                        };
+                        for key in move_contents(&from, &to)? {
+                            changed(format!(
+                                "move {}/{} to {}{}",
+                                rule.path, key, to_prefix, key
+                            ));
+                        }
+                    }
+                }
+                Action::Require => {
+                    if matching(final_path, &rule.path)?.is_empty() {
+                        anyhow::bail!(
+                            "Could not find {} in the package. Its structure might be unexpected; see {} to adjust the layout rules.",
+                            rule.path,
+                            LAYOUT_FILE
+                        );
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Rule {
+    fn applies(&self, stage: Stage, source: &BuildSource) -> bool {
+        let arch = if source.is_x86 { Arch::X86 } else { Arch::X64 };
+        let stream = if source.is_stable {
+            Stream::Stable
+        } else {
+            Stream::Dev
+        };
+        self.stage == stage
+            && self.arch.is_none_or(|rule_arch| rule_arch == arch)
+            && self.stream.is_none_or(|rule_stream| rule_stream == stream)
+            && self
+                .symbols
+                .is_none_or(|symbols| symbols == source.symbols_url.is_some())
+    }
+}
+
+/// Files below `root` matching a pattern, as forward-slash relative paths.
+fn matching(root: &Path, pattern: &str) -> Result<Vec<String>> {
+    Ok(list_files(root)
+        .map_err(|e| Error::filesystem(root, e))?
+        .iter()
+        .map(|relative| path_key(relative))
+        .filter(|key| glob_match(pattern, key))
+        .collect())
+}
+
+/// Moves everything inside `from` into `to`, replacing what's there, then removes `from`.
+/// Returns the relative paths that were moved.
+fn move_contents(from: &Path, to: &Path) -> Result<Vec<String>> {
+    let mut moved = Vec::new();
+    for relative in list_files(from).map_err(|e| Error::filesystem(from, e))? {
+        let dest_path = to.join(&relative);
+        if let Some(parent) = dest_path.parent() {
+            fs::create_dir_all(parent).map_err(|e| Error::filesystem(parent, e))?;
+        }
+        if dest_path.exists() {
+            fs::remove_file(&dest_path).map_err(|e| Error::filesystem(&dest_path, e))?;
+        }
+        let source_path = from.join(&relative);
+        fs::rename(&source_path, &dest_path).map_err(|e| Error::filesystem(&source_path, e))?;
+        moved.push(path_key(&relative));
+    }
+    fs::remove_dir_all(from).map_err(|e| Error::filesystem(from, e))?;
+    Ok(moved)
+}
+
+/// Case-insensitive glob match of a forward-slash path, where `*` stays within one
+/// path segment and a `**` segment matches any number of them.
+fn glob_match(pattern: &str, path: &str) -> bool {
+    let pattern: Vec<&str> = pattern.split('/').collect();
+    let path: Vec<&str> = path.split('/').collect();
+    match_segments(&pattern, &path)
+}
+
+fn match_segments(pattern: &[&str], path: &[&str]) -> bool {
+    match (pattern.first(), path.first()) {
+        (None, None) => true,
+        (Some(&"**"), _) => {
+            match_segments(&pattern[1..], path)
+                || (!path.is_empty() && match_segments(pattern, &path[1..]))
+        }
+        (Some(segment), Some(name)) => {
+            match_segment(segment.as_bytes(), name.as_bytes())
+                && match_segments(&pattern[1..], &path[1..])
+        }
+        _ => false,
+    }
+}
+
+fn match_segment(pattern: &[u8], name: &[u8]) -> bool {
+    match (pattern.first(), name.first()) {
+        (None, None) => true,
+        (Some(b'*'), _) => {
+            match_segment(&pattern[1..], name)
+                || (!name.is_empty() && match_segment(pattern, &name[1..]))
+        }
+        (Some(p), Some(n)) => p.eq_ignore_ascii_case(n) && match_segment(&pattern[1..], &name[1..]),
+        _ => false,
+    }
+}
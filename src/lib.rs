@@ -0,0 +1,117 @@
+//! Downloads RTX Remix builds from GitHub and installs them into game folders.
+//!
+//! The `rtx_remix_downloader` binary is a command line front end to this crate; launchers
+//! can call the same steps directly:
+//!
+//! - [`github`] finds stable releases, dev builds and pull request builds,
+//! - [`download`] fetches and extracts their packages, reporting to a [`progress::Reporter`],
+//! - [`layout`] arranges the extracted files for the game's architecture,
+//! - [`install`] runs the whole flow from [`install::InstallOptions`] and deploys the result.
+
+use anyhow::Result;
+use clap::ValueEnum;
+use reqwest::blocking::Client;
+
+// Declared first so its status! and report! macros are visible in the other modules
+#[macro_use]
+pub mod output;
+
+pub mod builds;
+pub mod catalog;
+pub mod config;
+pub mod conflicts;
+pub mod deploy;
+pub mod download;
+pub mod error;
+pub mod files;
+pub mod game;
+pub mod github;
+pub mod http;
+pub mod install;
+pub mod layout;
+pub mod lockfile;
+pub mod manifest;
+pub mod notes;
+pub mod pe;
+pub mod plan;
+pub mod profiles;
+pub mod progress;
+
+use catalog::Repo;
+
+// === Constants ===
+pub const BUILD_TYPES: [&str; 3] = ["release", "debugoptimized", "debug"];
+pub const DXVK_REMIX_REPO: &str = "NVIDIAGameWorks/dxvk-remix";
+pub const BRIDGE_REMIX_REPO: &str = "NVIDIAGameWorks/bridge-remix";
+pub const RTX_REMIX_REPO: &str = "NVIDIAGameWorks/rtx-remix";
+
+// Artifact name of the unified x86 package, next to the build type
+pub const X86_ARTIFACT: &str = "rtx-remix-for-x86-games";
+
+// Configuration files to download: name, repo, path in the repo, destination
+pub const ADDITIONAL_FILES: [(&str, Repo, &str, &str); 2] = [
+    ("dxvk.conf", Repo::Dxvk, "dxvk.conf", ""),
+    ("bridge.conf", Repo::Bridge, "bridge.conf", ".trex"),
+];
+
+// Files bridge-remix builds and where they go in an x86 install. The client d3d9.dll
+// must be 32-bit, which tells it apart from the 64-bit runtime's d3d9.dll.
+pub const BRIDGE_FILES: [(&str, &str); 3] = [
+    ("d3d9.dll", ""),
+    ("NvRemixLauncher32.exe", ""),
+    ("NvRemixBridge.exe", ".trex"),
+];
+
+// License files to download: name, repo, path in the repo, and the only arch needing it
+pub const LICENSES: [(&str, Repo, &str, Option<Arch>); 4] = [
+    ("LICENSE.txt", Repo::Rtx, "LICENSE.txt", None),
+    (
+        "ThirdPartyLicenses-dxvk.txt",
+        Repo::Dxvk,
+        "ThirdPartyLicenses.txt",
+        Some(Arch::X86),
+    ),
+    (
+        "ThirdPartyLicenses-bridge.txt",
+        Repo::Bridge,
+        "ThirdPartyLicenses.txt",
+        Some(Arch::X86),
+    ),
+    // x64 installs have no bridge, so only the DXVK license is needed
+    (
+        "ThirdPartyLicenses.txt",
+        Repo::Dxvk,
+        "ThirdPartyLicenses.txt",
+        Some(Arch::X64),
+    ),
+];
+
+// Where builds and files are fetched from, unless the catalog says otherwise
+pub const GITHUB_API_URL: &str = "https://api.github.com";
+pub const GITHUB_RAW_URL: &str = "https://raw.githubusercontent.com";
+pub const NIGHTLY_LINK_URL: &str = "https://nightly.link";
+
+// dxwrapper's D3D8 binaries and license
+pub const DXWRAPPER_BINARIES_URL: &str =
+    "https://nightly.link/elishacloud/dxwrapper/workflows/ci/master/dx8%20game%20binaries.zip";
+pub const DXWRAPPER_LICENSE_URL: &str =
+    "https://raw.githubusercontent.com/elishacloud/dxwrapper/refs/heads/master/License.txt";
+
+// SHA-256s of wrapper DLLs that carry neither version info nor a recognisable string,
+// and the wrapper each one is. Only hashes of builds checked by hand belong here; the
+// catalog's `wrapper_hashes` adds more without a new release.
+pub const WRAPPER_HASHES: [(&str, &str); 0] = [];
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Arch {
+    X86,
+    X64,
+}
+
+/// The HTTP client every request is sent with.
+pub fn build_client() -> Result<Client> {
+    Ok(Client::builder()
+        .user_agent("RTX Remix Downloader")
+        .build()?)
+}
@@ -0,0 +1,119 @@
+//! Lockfiles pinning an install to an exact build and the hash of everything it downloads,
+//! so a team can reproduce the same install with `install --locked`.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::error::Error;
+use crate::manifest::BuildSource;
+
+pub const DEFAULT_LOCKFILE: &str = "remix-lock.json";
+
+#[derive(Serialize, Deserialize)]
+pub struct Lockfile {
+    pub source: BuildSource,
+    /// Every URL the install downloaded, in download order
+    pub files: Vec<LockedFile>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct LockedFile {
+    pub url: String,
+    pub sha256: String,
+}
+
+impl Lockfile {
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = fs::read_to_string(path).map_err(|e| Error::filesystem(path, e))?;
+        Ok(serde_json::from_str(&contents).map_err(|e| {
+            Error::invalid(format!(
+                "Could not parse lockfile {}: {}",
+                path.display(),
+                e
+            ))
+        })?)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        fs::write(path, serde_json::to_string_pretty(self)?)
+            .map_err(|e| Error::filesystem(path, e))?;
+        Ok(())
+    }
+}
+
+/// Hashes of the files an install downloaded, optionally checked against a lockfile.
+#[derive(Default)]
+pub struct DownloadLog {
+    pinned: Option<BTreeMap<String, String>>,
+    downloaded: Vec<LockedFile>,
+}
+
+impl DownloadLog {
+    /// A log that rejects any download the lockfile doesn't list with the same hash.
+    pub fn pinned(lockfile: &Lockfile) -> Self {
+        Self {
+            pinned: Some(
+                lockfile
+                    .files
+                    .iter()
+                    .map(|file| (file.url.clone(), file.sha256.clone()))
+                    .collect(),
+            ),
+            downloaded: Vec::new(),
+        }
+    }
+
+    pub fn record(&mut self, url: &str, sha256: String) -> Result<()> {
+        if let Some(pinned) = &self.pinned {
+            match pinned.get(url) {
+                None => anyhow::bail!(Error::integrity(url, "is not in the lockfile")),
+                Some(expected) if *expected != sha256 => {
+                    anyhow::bail!(Error::integrity(
+                        url,
+                        format!(
+                            "no longer matches the lockfile (expected sha256 {}, got {})",
+                            expected, sha256
+                        )
+                    ))
+                }
+                Some(_) => {}
+            }
+        }
+        self.downloaded.push(LockedFile {
+            url: url.to_string(),
+            sha256,
+        });
+        Ok(())
+    }
+
+    /// Builds the lockfile for the install, failing if a pinned file was never downloaded.
+    pub fn finish(self, source: &BuildSource) -> Result<Lockfile> {
+        if let Some(pinned) = &self.pinned {
+            let missing: Vec<&String> = pinned
+                .keys()
+                .filter(|url| !self.downloaded.iter().any(|file| &file.url == *url))
+                .collect();
+            if !missing.is_empty() {
+                anyhow::bail!(Error::integrity(
+                    "The install",
+                    format!(
+                        "no longer downloads files listed in the lockfile: {}",
+                        missing
+                            .iter()
+                            .map(|url| url.as_str())
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    )
+                ));
+            }
+        }
+        Ok(Lockfile {
+            source: source.clone(),
+            files: self.downloaded,
+        })
+    }
+}
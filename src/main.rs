@@ -1,647 +1,662 @@
-use std::fs;
-use std::io::{self, Read, Write};
+//! Command line front end; the work is done by the `rtx_remix_downloader` library.
+
+use std::io;
 use std::path::{Path, PathBuf};
 
-use anyhow::{Context, Result};
+use anyhow::Result;
+use clap::{Args, Parser, Subcommand};
 use colored::*;
-use indicatif::{ProgressBar, ProgressStyle};
-use reqwest::blocking::Client;
-use serde_json::Value;
-
-// === Constants ===
-const BUILD_TYPES: [&str; 3] = ["release", "debugoptimized", "debug"];
-const DXVK_REMIX_REPO: &str = "NVIDIAGameWorks/dxvk-remix";
-
-// Configuration files to download
-const ADDITIONAL_FILES: [(&str, &str, &str); 2] = [
-    (
-        "dxvk.conf",
-        "https://raw.githubusercontent.com/NVIDIAGameWorks/dxvk-remix/main/dxvk.conf",
-        "",
-    ),
-    (
-        "bridge.conf",
-        "https://raw.githubusercontent.com/NVIDIAGameWorks/bridge-remix/refs/heads/main/bridge.conf",
-        ".trex",
-    ),
-];
-
-// License files to download
-const LICENSES: [(&str, &str); 3] = [
-    ("LICENSE.txt", "https://raw.githubusercontent.com/NVIDIAGameWorks/rtx-remix/refs/heads/main/LICENSE.txt"),
-    ("ThirdPartyLicenses-dxvk.txt", "https://raw.githubusercontent.com/NVIDIAGameWorks/dxvk-remix/refs/heads/main/ThirdPartyLicenses.txt"),
-    ("ThirdPartyLicenses-bridge.txt", "https://raw.githubusercontent.com/NVIDIAGameWorks/bridge-remix/refs/heads/main/ThirdPartyLicenses.txt"),
-];
-
-fn main() {
-    // Run the main logic and handle any errors
-    if let Err(e) = run_main() {
-        eprintln!("{}", format!("Error: {}", e).red());
-        // Keep console open on error
-        println!("\nPress Enter to exit...");
-        let mut input = String::new();
-        let _ = io::stdin().read_line(&mut input);
-        std::process::exit(1);
-    }
+use serde_json::json;
+
+use rtx_remix_downloader::error::Error;
+use rtx_remix_downloader::http::Http;
+use rtx_remix_downloader::install::{
+    check_for_update, detect_dx8_wrapper, detect_game_arch, display_path, install, parse_origin,
+    print_install_plan, ConflictAction, InstallOptions, Origin,
+};
+use rtx_remix_downloader::lockfile::{self, Lockfile};
+use rtx_remix_downloader::progress::Reporter;
+use rtx_remix_downloader::{
+    build_client, builds, catalog, config, deploy, layout, manifest, output, profiles, progress,
+    status, Arch, BUILD_TYPES,
+};
+
+/// Where installs are downloaded and assembled, in the working folder
+const REMIX_DIR: &str = "remix";
+
+#[derive(Parser)]
+#[command(about = "Downloads the latest RTX Remix builds from GitHub")]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+    /// URL or path of a package catalog overriding the built-in repos, artifacts and files
+    #[arg(long, global = true)]
+    catalog: Option<String>,
+    /// SHA-256 the catalog must have; required for remote catalogs
+    #[arg(long, global = true, requires = "catalog")]
+    catalog_sha256: Option<String>,
+    /// Print JSON events on stdout for launchers, moving human-readable messages to stderr
+    #[arg(long, global = true, value_enum, default_value = "human")]
+    output: output::Format,
 }
 
-fn run_main() -> Result<()> {
-    println!("{}", "RTX Remix Download Script v0.3.0.1".green().bold());
+#[derive(Subcommand)]
+enum Command {
+    /// Download a build without prompting, optionally installing it into a game folder
+    Install(InstallArgs),
+    /// Remove Remix from a game folder and restore the files it replaced
+    Uninstall {
+        /// Game folder Remix was installed into
+        game_dir: PathBuf,
+    },
+    /// Check an install against the hashes recorded when it was downloaded
+    Verify {
+        /// Remix folder or game folder to check
+        #[arg(default_value = "remix")]
+        path: PathBuf,
+    },
+    /// Restore missing or corrupted files by re-fetching the installed build
+    Repair {
+        /// Remix folder or game folder to repair
+        #[arg(default_value = "remix")]
+        path: PathBuf,
+    },
+    /// Read or change settings in an installed dxvk.conf, rtx.conf or bridge.conf
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+        /// Remix folder or game folder holding the configs
+        #[arg(long, global = true, default_value = "remix")]
+        path: PathBuf,
+        /// Config file to use
+        #[arg(
+            long,
+            global = true,
+            default_value = "dxvk.conf",
+            value_parser = ["dxvk.conf", "rtx.conf", "bridge.conf"]
+        )]
+        file: String,
+        /// Use the settings under this [section] instead of the global ones
+        #[arg(long, global = true, default_value = "")]
+        section: String,
+    },
+    /// Print the built-in package layout rules, to start a remix-layout.toml from
+    Layout,
+    /// Show recent stable releases and dev builds without installing anything
+    #[command(visible_alias = "search")]
+    List {
+        /// Only show builds whose tag, run number, commit, message or file names match
+        query: Option<String>,
+        /// How many releases and dev runs to look at
+        #[arg(long, default_value_t = 10)]
+        limit: usize,
+        /// Print the builds as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Reinstall games from the profiles file
+    Update {
+        /// Profiles to update
+        #[arg(required_unless_present = "all")]
+        profiles: Vec<String>,
+        /// Update every profile in the file
+        #[arg(long, conflicts_with = "profiles")]
+        all: bool,
+        /// File the profiles are read from
+        #[arg(long, default_value = profiles::DEFAULT_PROFILES_FILE)]
+        profiles_file: PathBuf,
+    },
+}
 
-    // First ask about stable vs development
-    println!("\nChoose build stream:");
-    println!(
-        "{}. Stable Release (Use these for the most stable experience)",
-        "1".yellow()
-    );
-    println!(
-        "{}. Development Build (Use this for the latest features, but it may be unstable)",
-        "2".yellow()
-    );
+#[derive(Subcommand)]
+enum ConfigAction {
+    /// Print the current value of an option
+    Get { key: String },
+    /// Set an option, refusing names upstream doesn't document
+    Set {
+        key: String,
+        value: String,
+        /// Set the option even if it isn't a known one
+        #[arg(long)]
+        force: bool,
+    },
+    /// Remove an option so its default applies again
+    Unset { key: String },
+    /// List options in every config file that upstream doesn't document
+    Check,
+    /// Show options added, removed or changed in upstream dxvk.conf since the install
+    Diff {
+        /// Compare against this dxvk.conf instead of the latest one on GitHub
+        #[arg(long)]
+        against: Option<PathBuf>,
+    },
+}
 
-    let mut input = String::new();
-    io::stdin().read_line(&mut input)?;
-    let is_stable = match input.trim() {
-        "1" => true,
-        "2" => false,
-        _ => {
-            println!("Invalid selection, defaulting to stable release");
-            true
-        }
-    };
+#[derive(Args)]
+struct InstallArgs {
+    /// Download the latest development build instead of the stable release
+    #[arg(long)]
+    dev: bool,
+    /// Where the dxvk-remix runtime comes from: stable, dev, pr:<number> or a local .zip
+    #[arg(long, value_parser = parse_origin, conflicts_with = "dev")]
+    runtime: Option<Origin>,
+    /// Where the x86 bridge comes from, if not the same place as the runtime
+    #[arg(long, value_parser = parse_origin)]
+    bridge: Option<Origin>,
+    /// Game architecture, detected from --game-exe when given and x86 otherwise
+    #[arg(long, value_enum)]
+    arch: Option<Arch>,
+    /// Build type to download
+    #[arg(long, default_value = "release", value_parser = BUILD_TYPES)]
+    build_type: String,
+    /// Copy the downloaded files into this game folder, backing up anything they replace
+    #[arg(long)]
+    game_dir: Option<PathBuf>,
+    /// Game executable to detect the architecture from; also installs next to it
+    #[arg(long)]
+    game_exe: Option<PathBuf>,
+    /// Move conflicting graphics wrappers such as ReShade out of the game folder
+    #[arg(long)]
+    move_conflicts: bool,
+    /// Keep debug symbols and download the matching symbols package, for crash reports
+    #[arg(long)]
+    with_symbols: bool,
+    /// Install the game described by this profile instead of using the options above
+    #[arg(
+        long,
+        conflicts_with_all = ["dev", "runtime", "bridge", "arch", "build_type", "game_dir", "game_exe", "move_conflicts", "with_symbols", "locked", "write_lock"]
+    )]
+    profile: Option<String>,
+    /// File the profiles are read from
+    #[arg(long, default_value = profiles::DEFAULT_PROFILES_FILE, requires = "profile")]
+    profiles_file: PathBuf,
+    /// Record the resolved build and the hash of every downloaded file in the lockfile
+    #[arg(long)]
+    write_lock: bool,
+    /// Install exactly the build in the lockfile, failing if any download no longer matches it
+    #[arg(long, conflicts_with_all = ["dev", "runtime", "bridge", "arch", "build_type", "with_symbols", "write_lock"])]
+    locked: bool,
+    /// Lockfile to write with --write-lock or read with --locked
+    #[arg(long, default_value = lockfile::DEFAULT_LOCKFILE)]
+    lockfile: PathBuf,
+    /// Print what the install would download and change, without touching the game folder
+    #[arg(long, conflicts_with_all = ["profile", "write_lock"])]
+    dry_run: bool,
+    /// Only check whether a newer build than the installed one is available, exiting with
+    /// code 5 if so
+    #[arg(long, conflicts_with_all = ["profile", "write_lock", "locked", "dry_run", "move_conflicts"])]
+    check: bool,
+}
 
-    // Ask about game architecture type
-    println!("\nChoose game type:");
-    println!("{}. 32-bit (x86) Games (Most older games)", "1".yellow());
-    println!("{}. 64-bit (x64) Games (More modern games)", "2".yellow());
+fn main() {
+    let cli = Cli::parse();
+    let interactive = cli.command.is_none();
+    output::set_format(cli.output);
 
-    let mut input = String::new();
-    io::stdin().read_line(&mut input)?;
-    let is_x86 = match input.trim() {
-        "1" => true,
-        "2" => false,
-        _ => {
-            println!("Invalid selection, defaulting to x86");
-            true
+    // Run the main logic and handle any errors
+    let mut reporter = progress::console();
+    let result = build_client().and_then(|client| {
+        let reporter = reporter.as_mut();
+        if let Some(location) = &cli.catalog {
+            catalog::init(&client, location, cli.catalog_sha256.as_deref(), reporter);
+        }
+        match cli.command {
+            None => run_main(&client, reporter).map(|_| output::Exit::Success),
+            Some(Command::Install(args)) if args.check => run_check(&client, args, reporter),
+            Some(command) => run_command(&client, command, reporter).map(|_| output::Exit::Success),
         }
+    });
+
+    let exit = match &result {
+        Ok(exit) => *exit,
+        Err(e) => output::exit_for(e),
     };
+    output::event(
+        "result",
+        json!({
+            "success": result.is_ok(),
+            "exit_code": exit as i32,
+            "error": result.as_ref().err().map(|e| format!("{:#}", e)),
+        }),
+    );
 
-    // Ask for build type
-    println!("\nChoose a build type (type the number and press Enter):");
-    for (i, build_type) in BUILD_TYPES.iter().enumerate() {
-        println!("{}. {}", (i + 1).to_string().yellow(), build_type);
+    if let Err(e) = result {
+        eprintln!("{}", format!("Error: {}", e).red());
+        if interactive {
+            // Keep console open on error
+            status!("\nPress Enter to exit...");
+            let mut input = String::new();
+            let _ = io::stdin().read_line(&mut input);
+        }
     }
+    std::process::exit(exit as i32);
+}
 
-    let mut input = String::new();
-    io::stdin().read_line(&mut input)?;
-    let build_type = BUILD_TYPES[input.trim().parse::<usize>()? - 1];
-
-    let client = Client::builder()
-        .user_agent("RTX Remix Downloader")
-        .build()?;
-
-    // Create and clean the "remix" folder in the current working directory
-    let remix_path = PathBuf::from("remix");
-    cleanup_existing_directory(&remix_path)?;
-    let final_path = remix_path.canonicalize()?;
-    if is_stable {
-        println!(
-            "{}",
-            format!("\nDownloading stable {} build...", build_type).cyan()
-        );
-
-        // Fetch and download stable release
-        let (asset_name, download_url) = fetch_latest_stable_release(&client, build_type)?;
-        let stable_zip = final_path.join("stable-release.zip");
-
-        println!("Downloading stable release from GitHub...");
-        download_file(&client, &download_url, &stable_zip)?;
-
-        println!("Extracting stable release...");
-        let file = fs::File::open(&stable_zip)?;
-        let mut archive = zip::ZipArchive::new(file)?;
-        archive.extract(&final_path)?;
-
-        // Cleanup zip file
-        fs::remove_file(stable_zip)?;
-
-        // Clean up debug files
-        cleanup_debug_files(&final_path)?;
-
-        // Write build info with actual package name
-        write_build_names(&final_path, &[asset_name])?;
-
-        if is_x86 {
-            // Remove d3d8to9.dll and its license file for stable x86 builds
-            let d3d8to9_path = final_path.join("d3d8to9.dll");
-            let d3d8to9_license_path = final_path.join("ThirdPartyLicenses-d3d8to9.txt");
-
-            if d3d8to9_path.exists() {
-                if let Err(e) = fs::remove_file(&d3d8to9_path) {
-                    eprintln!(
-                        "{}",
-                        format!("Warning: Could not remove d3d8to9.dll: {}", e).yellow()
-                    );
-                } else {
-                    println!("{}", "Removed d3d8to9.dll".cyan());
-                }
+fn run_command(client: &dyn Http, command: Command, reporter: &mut dyn Reporter) -> Result<()> {
+    match command {
+        Command::Install(args) => run_install(client, args, reporter),
+        Command::Layout => {
+            if output::is_json() {
+                output::event("layout", json!({ "rules": layout::DEFAULT_RULES }));
+            } else {
+                print!("{}", layout::DEFAULT_RULES);
             }
-
-            if d3d8to9_license_path.exists() {
-                if let Err(e) = fs::remove_file(&d3d8to9_license_path) {
-                    eprintln!(
-                        "{}",
-                        format!(
-                            "Warning: Could not remove ThirdPartyLicenses-d3d8to9.txt: {}",
-                            e
-                        )
-                        .yellow()
-                    );
-                } else {
-                    println!("{}", "Removed ThirdPartyLicenses-d3d8to9.txt".cyan());
-                }
+            Ok(())
+        }
+        Command::List { query, limit, json } => {
+            if !json && !output::is_json() {
+                status!("{}", "Fetching available builds...".cyan());
             }
-
-            // Download and extract dx8 binaries for x86
-            download_and_extract_dx8_binaries(&client, &final_path)?;
-            // Download all additional files and licenses
-            download_additional_files(&client, &final_path)?;
-            download_licenses(&client, &final_path)?;
-        } else {
-            // For x64, reorganize files and only keep DXVK-related files
-            reorganize_x64_files(&final_path)?;
-            // Download only DXVK-related licenses
-            download_x64_licenses(&client, &final_path)?;
+            let listing = builds::list(client, query.as_deref(), limit)?;
+            if output::is_json() {
+                output::event("builds", serde_json::to_value(&listing)?);
+            } else if json {
+                println!("{}", serde_json::to_string_pretty(&listing)?);
+            } else {
+                builds::print_table(&listing, reporter);
+            }
+            Ok(())
         }
-    } else if is_x86 {
-        // Fetch and download unified x86 package
-        let (artifact_name, download_url) = fetch_x86_unified_artifact(&client, build_type)?;
-        let unified_zip = final_path.join("rtx-remix-x86.zip");
-
-        println!("Downloading unified x86 package: {}", artifact_name);
-        download_file(&client, &download_url, &unified_zip)?;
-
-        println!("Extracting unified package...");
-        let file = fs::File::open(&unified_zip)?;
-        let mut archive = zip::ZipArchive::new(file)?;
-        archive.extract(&final_path)?;
-
-        // Cleanup zip file
-        fs::remove_file(unified_zip)?;
-
-        // Clean up debug files
-        cleanup_debug_files(&final_path)?;
-
-        // Download and extract dx8 binaries for x86
-        download_and_extract_dx8_binaries(&client, &final_path)?;
-
-        // Download additional files and licenses
-        download_additional_files(&client, &final_path)?;
-        download_licenses(&client, &final_path)?;
-
-        // Write build info
-        write_build_names(&final_path, &[artifact_name])?;
-    } else {
-        // Fetch and download x64 package
-        let (artifact_name, download_url) = fetch_x64_artifact(&client, build_type)?;
-        let x64_zip = final_path.join("rtx-remix-x64.zip");
-
-        println!("Downloading x64 package: {}", artifact_name);
-        download_file(&client, &download_url, &x64_zip)?;
-
-        println!("Extracting x64 package...");
-        let file = fs::File::open(&x64_zip)?;
-        let mut archive = zip::ZipArchive::new(file)?;
-        archive.extract(&final_path)?;
-
-        // Cleanup zip file
-        fs::remove_file(x64_zip)?;
-
-        // Clean up debug files
-        cleanup_debug_files(&final_path)?;
-
-        // For x64, only download DXVK-related licenses
-        download_x64_licenses(&client, &final_path)?;
-
-        // Write build info
-        write_build_names(&final_path, &[artifact_name])?;
+        Command::Update {
+            profiles,
+            profiles_file,
+            ..
+        } => run_profiles(client, &profiles_file, &profiles, reporter),
+        Command::Uninstall { game_dir } => deploy::uninstall(&game_dir, reporter),
+        Command::Verify { path } => manifest::verify(&path, reporter),
+        Command::Repair { path } => {
+            manifest::repair(client, &path, layout_file().as_deref(), reporter)
+        }
+        Command::Config {
+            action,
+            path,
+            file,
+            section,
+        } => match action {
+            ConfigAction::Get { key } => {
+                print_setting(&config::get(&path, &file, &section, &key, reporter)?);
+                Ok(())
+            }
+            ConfigAction::Set { key, value, force } => {
+                config::set(&path, &file, &section, &key, &value, force, reporter)
+            }
+            ConfigAction::Unset { key } => config::unset(&path, &file, &section, &key, reporter),
+            ConfigAction::Check => config::check(&path, reporter),
+            ConfigAction::Diff { against } => {
+                let diff = config::diff_installed(client, &path, against.as_deref(), reporter)?;
+                config::print_option_diff("dxvk.conf", &diff, reporter);
+                Ok(())
+            }
+        },
     }
+}
 
-    println!("{}", "Download complete!".green().bold());
-    println!("You can find the latest RTX Remix install in:");
-    println!("{}", clickable_path(&final_path));
-    println!("{}", "RTX Remix install guide:".yellow());
-    println!(
-        "{}",
-        "https://github.com/NVIDIAGameWorks/rtx-remix/wiki/runtime-user-guide".cyan()
-    );
+fn run_main(client: &dyn Http, reporter: &mut dyn Reporter) -> Result<()> {
+    status!("{}", "RTX Remix Download Script v0.3.0.1".green().bold());
+
+    let options = prompt_install_options(reporter)?;
+    install(&options, client, reporter)?;
 
     // Keep the console open
-    println!("\nPress Enter to exit...");
+    status!("\nPress Enter to exit...");
     let mut input = String::new();
     io::stdin().read_line(&mut input)?;
 
     Ok(())
 }
 
-// === GitHub API Interaction Functions ===
-fn fetch_latest_stable_release(client: &Client, build_type: &str) -> Result<(String, String)> {
-    println!("{}", "Fetching latest stable release information...".cyan());
-
-    let releases_url = "https://api.github.com/repos/NVIDIAGameWorks/rtx-remix/releases/latest";
-    let response: Value = client.get(releases_url).send()?.json()?;
-
-    let asset = response["assets"]
-        .as_array()
-        .and_then(|assets| {
-            assets.iter().find(|asset| {
-                asset["name"].as_str().is_some_and(|name| {
-                    // Match the exact pattern: ends with build_type.zip
-                    // and explicitly exclude -symbols
-                    name.ends_with(&format!("-{}.zip", build_type)) && !name.contains("-symbols")
-                })
-            })
-        })
-        .context("No suitable release package found")?;
-
-    let download_url = asset["browser_download_url"]
-        .as_str()
-        .context("No download URL found")?
-        .to_string();
-
-    let asset_name = asset["name"]
-        .as_str()
-        .context("No asset name found")?
-        .to_string();
-
-    println!(
-        "{}",
-        format!("Found stable release: {} ({})", asset_name, download_url).green()
-    );
-
-    Ok((asset_name, download_url))
-}
-
-fn fetch_x86_unified_artifact(client: &Client, build_type: &str) -> Result<(String, String)> {
-    println!(
-        "{}",
-        format!("Fetching unified x86 package ({} build)...", build_type).cyan()
-    );
-
-    let runs_url = format!(
-        "https://api.github.com/repos/{}/actions/runs?branch=main", // Added ?branch=main
-        DXVK_REMIX_REPO
-    );
-    let runs: Value = client.get(runs_url).send()?.json()?;
-
-    // Find the latest successful run on the main branch
-    let latest_successful_run = runs["workflow_runs"]
-        .as_array()
-        .and_then(|runs_array| {
-            runs_array
-                .iter()
-                .filter(|run| run["conclusion"] == "success") // No need to filter branch here anymore
-                .max_by_key(|run| run["run_number"].as_u64().unwrap_or(0)) // Get the latest by run number
-        })
-        .context("No successful run found on the main branch")?;
-
-    let artifacts_url = latest_successful_run["artifacts_url"]
-        .as_str()
-        .context("No artifacts URL found in the latest successful run")?;
-
-    let artifacts: Value = client.get(artifacts_url).send()?.json()?;
-
-    let artifact = artifacts["artifacts"]
-        .as_array()
-        .and_then(|artifacts_array| {
-            artifacts_array.iter().find(|a| {
-                a["name"].as_str().is_some_and(|name| {
-                    name.contains(build_type) && name.contains("rtx-remix-for-x86-games")
-                })
-            })
-        })
-        .context("No matching x86 unified artifact found")?;
-
-    let artifact_name = artifact["name"].as_str().unwrap().to_string();
-    let artifact_id = artifact["id"].as_u64().unwrap();
-
-    let download_url = format!(
-        "https://nightly.link/{}/actions/artifacts/{}.zip",
-        DXVK_REMIX_REPO, artifact_id
-    );
-
-    Ok((artifact_name, download_url))
-}
-
-fn fetch_x64_artifact(client: &Client, build_type: &str) -> Result<(String, String)> {
-    println!(
-        "{}",
-        format!("Fetching x64 package ({} build)...", build_type).cyan()
-    );
-
-    let runs_url = format!(
-        "https://api.github.com/repos/{}/actions/runs?branch=main", // Added ?branch=main
-        DXVK_REMIX_REPO
-    );
-    let runs: Value = client.get(runs_url).send()?.json()?;
-
-    // Find the latest successful run on the main branch
-    let latest_successful_run = runs["workflow_runs"]
-        .as_array()
-        .and_then(|runs_array| {
-            runs_array
-                .iter()
-                .filter(|run| run["conclusion"] == "success") // No need to filter branch here anymore
-                .max_by_key(|run| run["run_number"].as_u64().unwrap_or(0)) // Get the latest by run number
-        })
-        .context("No successful run found on the main branch")?;
-
-    let artifacts_url = latest_successful_run["artifacts_url"]
-        .as_str()
-        .context("No artifacts URL found in the latest successful run")?;
-
-    let artifacts: Value = client.get(artifacts_url).send()?.json()?;
-
-    let artifact = artifacts["artifacts"]
-        .as_array()
-        .and_then(|artifacts_array| {
-            artifacts_array.iter().find(|a| {
-                a["name"].as_str().is_some_and(|name| {
-                    name.contains(build_type) && !name.contains("x86") && !name.contains("symbols")
-                })
-            })
-        })
-        .context("No matching x64 artifact found")?;
-
-    let artifact_name = artifact["name"].as_str().unwrap().to_string();
-    let artifact_id = artifact["id"].as_u64().unwrap();
-
-    let download_url = format!(
-        "https://nightly.link/{}/actions/artifacts/{}.zip",
-        DXVK_REMIX_REPO, artifact_id
-    );
-
-    Ok((artifact_name, download_url))
+fn run_install(client: &dyn Http, args: InstallArgs, reporter: &mut dyn Reporter) -> Result<()> {
+    if let Some(profile) = args.profile {
+        return run_profiles(client, &args.profiles_file, &[profile], reporter);
+    }
+    let dry_run = args.dry_run;
+    let options = if args.locked {
+        let lockfile = Lockfile::load(&args.lockfile)?;
+        locked_options(args, lockfile)
+    } else {
+        install_options(args, reporter)
+    };
+    if dry_run {
+        return print_install_plan(&options, client, reporter);
+    }
+    install(&options, client, reporter).map(|_| ())
 }
 
-// === Download and File Operations ===
-fn download_file(client: &Client, url: &str, dest: &Path) -> Result<()> {
-    let mut response = client.get(url).send()?;
-    let total_size = response.content_length().unwrap_or(0);
-
-    let pb = ProgressBar::new(total_size);
-    pb.set_style(ProgressStyle::default_bar()
-        .template("{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {bytes}/{total_bytes} ({eta})")
-        .unwrap()
-        .progress_chars("#>-"));
-
-    let mut file = fs::File::create(dest)?;
-
-    let mut buffer = [0; 8192];
-    while let Ok(size) = response.read(&mut buffer) {
-        if size == 0 {
-            break;
+/// Installs each named profile, or all of them when none are named, carrying on past failures.
+fn run_profiles(
+    client: &dyn Http,
+    profiles_file: &Path,
+    names: &[String],
+    reporter: &mut dyn Reporter,
+) -> Result<()> {
+    let profiles = profiles::load(profiles_file)?;
+    let names: Vec<&String> = if names.is_empty() {
+        profiles.keys().collect()
+    } else {
+        names.iter().collect()
+    };
+    if names.is_empty() {
+        anyhow::bail!(Error::invalid(format!(
+            "No profiles found in {}",
+            profiles_file.display()
+        )));
+    }
+    for name in &names {
+        if !profiles.contains_key(*name) {
+            anyhow::bail!(Error::invalid(format!(
+                "No profile named {} in {}",
+                name,
+                profiles_file.display()
+            )));
         }
-        file.write_all(&buffer[..size])?;
-        pb.inc(size as u64);
-        pb.set_message("Downloading...");
     }
 
-    pb.finish_with_message("Download complete");
-    Ok(())
-}
-
-fn download_additional_files(client: &Client, final_path: &Path) -> Result<()> {
-    println!("{}", "Downloading additional files".cyan());
-    for (name, url, destination) in ADDITIONAL_FILES {
-        let dest_path = final_path.join(destination).join(name);
-        download_file(client, url, &dest_path)?;
-        println!("{}", format!("Downloaded {}", name).green());
+    let mut failed = Vec::new();
+    for name in &names {
+        let profile = &profiles[*name];
+        status!("\n{}", format!("=== Profile {} ===", name).green().bold());
+        let options = install_options(profile_args(profile), reporter);
+        let result = install(&options, client, reporter)
+            .and_then(|installed| profile.apply_overrides(&installed.path, reporter));
+        if let Err(e) = result {
+            eprintln!("{}", format!("Profile {} failed: {:#}", name, e).red());
+            failed.push(name.as_str());
+        }
     }
-    Ok(())
-}
 
-fn download_licenses(client: &Client, final_path: &Path) -> Result<()> {
-    println!("{}", "Downloading license files".cyan());
-    for (filename, url) in LICENSES {
-        let dest_path = final_path.join(filename);
-        download_file(client, url, &dest_path)?;
+    if !failed.is_empty() {
+        anyhow::bail!(
+            "{} of {} profiles failed: {}",
+            failed.len(),
+            names.len(),
+            failed.join(", ")
+        );
     }
-    println!("{}", "License files downloaded".green());
     Ok(())
 }
 
-fn download_x64_licenses(client: &Client, final_path: &Path) -> Result<()> {
-    println!("{}", "Downloading license files".cyan());
-    // Only download main license and DXVK license for x64 builds
-    let x64_licenses = [
-        ("LICENSE.txt", "https://raw.githubusercontent.com/NVIDIAGameWorks/rtx-remix/refs/heads/main/LICENSE.txt"),
-        ("ThirdPartyLicenses.txt", "https://raw.githubusercontent.com/NVIDIAGameWorks/dxvk-remix/refs/heads/main/ThirdPartyLicenses.txt"),
-    ];
-
-    for (filename, url) in x64_licenses {
-        let dest_path = final_path.join(filename);
-        download_file(client, url, &dest_path)?;
+fn profile_args(profile: &profiles::Profile) -> InstallArgs {
+    InstallArgs {
+        dev: profile.stream == profiles::Stream::Dev,
+        runtime: None,
+        bridge: None,
+        arch: profile.arch,
+        build_type: profile.build_type.clone(),
+        game_dir: profile.game_dir.clone(),
+        game_exe: profile.game_exe.clone(),
+        move_conflicts: profile.move_conflicts,
+        with_symbols: false,
+        profile: None,
+        profiles_file: PathBuf::new(),
+        write_lock: false,
+        locked: false,
+        lockfile: PathBuf::new(),
+        dry_run: false,
+        check: false,
     }
-    println!("{}", "License files downloaded".green());
-    Ok(())
 }
 
-fn download_and_extract_dx8_binaries(client: &Client, final_path: &Path) -> Result<()> {
-    println!("{}", "Downloading dx8 binaries".cyan());
-    let dx8_url =
-        "https://nightly.link/elishacloud/dxwrapper/workflows/ci/master/dx8%20game%20binaries.zip";
-    let dx8_zip_path = final_path.join("dx8_binaries.zip");
-    download_file(client, dx8_url, &dx8_zip_path)?;
-
-    println!("{}", "Extracting dx8 binaries".cyan());
-    let file = fs::File::open(&dx8_zip_path)?;
-    let mut archive = zip::ZipArchive::new(file)?;
-    archive.extract(final_path)?;
-
-    // Rename d3d8.dll to d3d8_off.dll
-    let d3d8_path = final_path.join("d3d8.dll");
-    if d3d8_path.exists() {
-        fs::rename(&d3d8_path, final_path.join("d3d8_off.dll"))?;
-    }
-
-    // Remove d3d8to9.dll
-    let d3d8to9_path = final_path.join("d3d8to9.dll");
-    if d3d8to9_path.exists() {
-        if let Err(e) = fs::remove_file(&d3d8to9_path) {
-            eprintln!(
-                "{}",
-                format!("Warning: Could not remove d3d8to9.dll: {}", e).yellow()
-            );
+/// Options reproducing the build recorded in a lockfile.
+fn locked_options(args: InstallArgs, lockfile: Lockfile) -> InstallOptions {
+    let source = &lockfile.source;
+    status!(
+        "{}",
+        format!(
+            "Installing {} from {}",
+            source.package_name,
+            display_path(&args.lockfile)
+        )
+        .cyan()
+    );
+    InstallOptions {
+        runtime: if source.is_stable {
+            Origin::Stable
         } else {
-            println!("{}", "Removed d3d8to9.dll".cyan());
-        }
+            Origin::Dev
+        },
+        bridge: None,
+        is_x86: source.is_x86,
+        build_type: source.build_type.clone(),
+        game_dir: target_dir(args.game_dir, args.game_exe.as_deref()),
+        dx8_wrapper: source.dx8_wrapper,
+        conflicts: conflict_action(args.move_conflicts),
+        with_symbols: source.symbols_url.is_some(),
+        locked: Some(lockfile),
+        write_lock: None,
+        confirm: false,
+        ask: None,
+        remix_dir: PathBuf::from(REMIX_DIR),
+        layout_file: layout_file(),
     }
-
-    // Download the dxwrapper license specifically here since it's related to these binaries
-    println!("{}", "Downloading dxwrapper license".cyan());
-    let dxwrapper_license_url =
-        "https://raw.githubusercontent.com/elishacloud/dxwrapper/refs/heads/master/License.txt";
-    let license_dest_path = final_path.join("ThirdPartyLicenses-dxwrapper.txt");
-    download_file(client, dxwrapper_license_url, &license_dest_path)?;
-
-    println!("{}", "Cleaning up dx8 binaries zip file".cyan());
-    fs::remove_file(dx8_zip_path)?;
-
-    Ok(())
 }
 
-// === File System Operations ===
-fn cleanup_existing_directory(path: &Path) -> Result<()> {
-    if path.exists() {
-        println!("{}", "Cleaning up existing installation...".cyan());
-        fs::remove_dir_all(path)?;
-    }
-    fs::create_dir_all(path)?;
-    Ok(())
+/// The game folder to install into, defaulting to the executable's folder.
+fn target_dir(game_dir: Option<PathBuf>, game_exe: Option<&Path>) -> Option<PathBuf> {
+    game_dir.or_else(|| game_exe.and_then(Path::parent).map(Path::to_path_buf))
 }
 
-fn cleanup_debug_files(dir: &Path) -> Result<()> {
-    let mut removed_files = 0;
-    cleanup_debug_files_recursive(dir, &mut removed_files)?;
-    if removed_files > 0 {
-        println!(
-            "{}",
-            format!(
-                "Cleaned up {} debugging symbol files and unnecessary files",
-                removed_files
-            )
-            .cyan()
-        );
+fn conflict_action(move_conflicts: bool) -> ConflictAction {
+    if move_conflicts {
+        ConflictAction::MoveAside
+    } else {
+        ConflictAction::Report
     }
-    Ok(())
 }
 
-fn cleanup_debug_files_recursive(dir: &Path, removed_files: &mut u32) -> Result<()> {
-    for entry in fs::read_dir(dir)? {
-        let entry = entry?;
-        let path = entry.path();
-
-        if path.is_dir() {
-            cleanup_debug_files_recursive(&path, removed_files)?;
-        } else {
-            let file_name = path.file_name().unwrap_or_default().to_string_lossy();
-
-            if path.extension().is_some_and(|ext| ext == "pdb")
-                || file_name == "CRC.txt"
-                || file_name == "artifacts_readme.txt"
-            {
-                if let Err(e) = fs::remove_file(&path) {
-                    eprintln!("Failed to remove file {}: {}", path.display(), e);
-                } else {
-                    *removed_files += 1;
-                }
+fn install_options(args: InstallArgs, reporter: &mut dyn Reporter) -> InstallOptions {
+    let detected = args
+        .game_exe
+        .as_deref()
+        .and_then(|game_exe| detect_game_arch(game_exe, reporter));
+    let is_x86 = match (args.arch, detected) {
+        (Some(arch), Some(detected_x86)) => {
+            let is_x86 = matches!(arch, Arch::X86);
+            if is_x86 != detected_x86 {
+                status!(
+                    "{}",
+                    "Overriding the detected architecture with --arch".yellow()
+                );
             }
+            is_x86
         }
+        (Some(arch), None) => matches!(arch, Arch::X86),
+        (None, Some(detected_x86)) => detected_x86,
+        (None, None) => true,
+    };
+
+    InstallOptions {
+        runtime: args.runtime.unwrap_or(if args.dev {
+            Origin::Dev
+        } else {
+            Origin::Stable
+        }),
+        bridge: args.bridge,
+        is_x86,
+        build_type: args.build_type,
+        dx8_wrapper: detect_dx8_wrapper(args.game_exe.as_deref(), is_x86, reporter),
+        game_dir: target_dir(args.game_dir, args.game_exe.as_deref()),
+        conflicts: conflict_action(args.move_conflicts),
+        with_symbols: args.with_symbols,
+        locked: None,
+        write_lock: args.write_lock.then_some(args.lockfile),
+        confirm: false,
+        ask: None,
+        remix_dir: PathBuf::from(REMIX_DIR),
+        layout_file: layout_file(),
     }
-    Ok(())
 }
 
-fn reorganize_x64_files(final_path: &Path) -> Result<()> {
-    println!("{}", "Reorganizing x64 files...".cyan());
+/// The layout rules in the working folder, when there are any.
+fn layout_file() -> Option<PathBuf> {
+    let path = PathBuf::from(layout::LAYOUT_FILE);
+    path.is_file().then_some(path)
+}
 
-    let trex_path = final_path.join(".trex");
-    if !trex_path.exists() {
-        // If .trex doesn't exist, maybe it's an older stable release structure?
-        // Check if dxvk.dll exists in the root as a fallback check.
-        if !final_path.join("dxvk.dll").exists() {
-            return Err(anyhow::anyhow!("Could not find .trex directory or dxvk.dll in the package root. Structure might be unexpected."));
-        }
-        // If dxvk.dll is in root, assume it's already somewhat organized, skip .trex steps.
-        println!(
-            "{}",
-            "Skipping .trex reorganization as dxvk.dll found in root.".yellow()
-        );
+/// Asks a yes or no question on the console; anything but yes or no takes `default`.
+fn ask(question: &str, default: bool) -> bool {
+    let choices = if default {
+        format!("{}/{}", "Y".yellow(), "n".yellow())
     } else {
-        // Remove nvremixbridge.exe if it exists within .trex
-        let bridge_path = trex_path.join("nvremixbridge.exe");
-        if bridge_path.exists() {
-            fs::remove_file(bridge_path)?;
-        }
+        format!("{}/{}", "y".yellow(), "N".yellow())
+    };
+    status!("{} ({})", question, choices);
+    let mut input = String::new();
+    if io::stdin().read_line(&mut input).is_err() {
+        return default;
+    }
+    match input.trim().to_ascii_lowercase() {
+        answer if answer.starts_with('y') => true,
+        answer if answer.starts_with('n') => false,
+        _ => default,
+    }
+}
 
-        // Move all files from .trex to root directory
-        for entry in fs::read_dir(&trex_path)? {
-            let entry = entry?;
-            let source_path = entry.path();
-            if source_path.is_file() {
-                let file_name = source_path.file_name().unwrap();
-                let dest_path = final_path.join(file_name);
-                // Overwrite if exists, as we prioritize files from .trex
-                if dest_path.exists() {
-                    fs::remove_file(&dest_path)?;
-                }
-                fs::rename(&source_path, &dest_path)?;
-            }
-        }
+fn prompt_install_options(reporter: &mut dyn Reporter) -> Result<InstallOptions> {
+    // First ask about stable vs development
+    status!("\nChoose build stream:");
+    status!(
+        "{}. Stable Release (Use these for the most stable experience)",
+        "1".yellow()
+    );
+    status!(
+        "{}. Development Build (Use this for the latest features, but it may be unstable)",
+        "2".yellow()
+    );
 
-        // Move the usd folder from .trex to root if it exists
-        let usd_path = trex_path.join("usd");
-        let new_usd_path = final_path.join("usd");
-        if usd_path.exists() {
-            // Remove existing usd folder in root if it exists, to avoid merge conflicts
-            if new_usd_path.exists() {
-                fs::remove_dir_all(&new_usd_path)?;
-            }
-            fs::rename(&usd_path, &new_usd_path)?;
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+    let is_stable = match input.trim() {
+        "1" => true,
+        "2" => false,
+        _ => {
+            status!("Invalid selection, defaulting to stable release");
+            true
         }
+    };
 
-        // Now remove the potentially empty .trex directory
-        fs::remove_dir_all(&trex_path)?;
+    // Optionally install straight into a game folder
+    status!(
+        "\nEnter the game's .exe to detect its type and install next to it, a game folder to install into, or leave empty to only download:"
+    );
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+    let game_path = match input.trim().trim_matches('"') {
+        "" => None,
+        path => Some(PathBuf::from(path)),
+    };
+    let (game_exe, game_dir) = match game_path {
+        Some(path) if path.is_file() => (Some(path.clone()), path.parent().map(Path::to_path_buf)),
+        other => (None, other),
+    };
+    let detected = game_exe
+        .as_deref()
+        .and_then(|game_exe| detect_game_arch(game_exe, reporter));
+
+    // Ask about game architecture type
+    status!("\nChoose game type:");
+    status!("{}. 32-bit (x86) Games (Most older games)", "1".yellow());
+    status!("{}. 64-bit (x64) Games (More modern games)", "2".yellow());
+    if detected.is_some() {
+        status!("Press Enter to use the detected type");
     }
 
-    // Remove any bridge, d3d8to9 or d3d8 related files from root (ensure clean state)
-    let files_to_remove = [
-        "nvremixbridge.exe",
-        "d3d8to9.dll",
-        "d3d8.dll",
-        "d3d8_off.dll",
-        "dxwrapper.dll",
-        "dxwrapper.ini",
-    ];
-    for file in files_to_remove.iter() {
-        let file_path = final_path.join(file);
-        if file_path.exists() {
-            fs::remove_file(file_path)?;
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+    let is_x86 = match (input.trim(), detected) {
+        ("1", _) => true,
+        ("2", _) => false,
+        ("", Some(detected_x86)) => detected_x86,
+        _ => {
+            status!("Invalid selection, defaulting to x86");
+            true
         }
+    };
+
+    // Ask for build type
+    status!("\nChoose a build type (type the number and press Enter):");
+    for (i, build_type) in BUILD_TYPES.iter().enumerate() {
+        status!("{}. {}", (i + 1).to_string().yellow(), build_type);
     }
 
-    println!("{}", "Files reorganized successfully for x64".green());
-    Ok(())
-}
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+    let build_type = BUILD_TYPES[input.trim().parse::<usize>()? - 1].to_string();
 
-// === Utility Functions ===
-fn write_build_names(final_path: &Path, build_names: &[String]) -> Result<()> {
-    let build_names_path = final_path.join("build-names.txt");
-    let mut file = fs::File::create(build_names_path)?;
-    for name in build_names {
-        writeln!(file, "{}", name)?;
-    }
-    println!(
-        "{}",
-        format!(
-            "Created build-names.txt with {} build names",
-            build_names.len()
-        )
-        .green()
-    );
-    Ok(())
+    Ok(InstallOptions {
+        runtime: if is_stable {
+            Origin::Stable
+        } else {
+            Origin::Dev
+        },
+        bridge: None,
+        is_x86,
+        build_type,
+        game_dir,
+        dx8_wrapper: detect_dx8_wrapper(game_exe.as_deref(), is_x86, reporter),
+        conflicts: ConflictAction::Ask,
+        with_symbols: false,
+        locked: None,
+        write_lock: None,
+        confirm: true,
+        ask: Some(Box::new(ask)),
+        remix_dir: PathBuf::from(REMIX_DIR),
+        layout_file: layout_file(),
+    })
 }
 
-fn display_path(path: &Path) -> String {
-    path.to_string_lossy().replace(r"\\?\", "")
+fn print_setting(setting: &config::Setting) {
+    if output::is_json() {
+        output::event("value", json!(setting));
+        return;
+    }
+    match (&setting.value, &setting.default) {
+        (Some(value), _) => status!("{}", value),
+        (None, Some(default)) => status!(
+            "{} is not set in {} (documented default: {})",
+            setting.key,
+            setting.path,
+            default
+        ),
+        (None, None) => status!("{} is not set in {}", setting.key, setting.path),
+    }
 }
 
-fn clickable_path(path: &Path) -> String {
-    let clean_path = display_path(path);
-    format!(
-        "\x1B]8;;file://{}\x07{}\x1B]8;;\x07",
-        clean_path, clean_path
-    )
-    .cyan()
-    .to_string()
+/// Checks for a newer build than the installed one, exiting with `UpdateAvailable` if so.
+fn run_check(
+    client: &dyn Http,
+    args: InstallArgs,
+    reporter: &mut dyn Reporter,
+) -> Result<output::Exit> {
+    let options = install_options(args, reporter);
+    let install_path = options.game_dir.as_deref().unwrap_or(&options.remix_dir);
+    let update = check_for_update(client, &options, reporter)?;
+    output::event("update", json!(update));
+
+    if !update.available {
+        status!(
+            "{}",
+            format!("{} is up to date", display_path(install_path)).green()
+        );
+        return Ok(output::Exit::Success);
+    }
+    match &update.installed {
+        Some(installed) => status!(
+            "{}",
+            format!(
+                "Update available: {} (installed: {})",
+                update.latest.package_name, installed.package_name
+            )
+            .yellow()
+        ),
+        None => status!(
+            "{}",
+            format!(
+                "Nothing installed in {}, {} is available",
+                display_path(install_path),
+                update.latest.package_name
+            )
+            .yellow()
+        ),
+    }
+    Ok(output::Exit::UpdateAvailable)
 }
@@ -0,0 +1,329 @@
+//! Install manifest recording where a build came from and the hash of every file,
+//! used by the `verify` and `repair` commands.
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use colored::*;
+use serde::{Deserialize, Serialize};
+
+use crate::config::MERGED_CONFIGS;
+use crate::deploy;
+use crate::error::Error;
+use crate::files::{list_files, path_key, sha256_file};
+use crate::http::Http;
+use crate::lockfile::DownloadLog;
+use crate::progress::Reporter;
+
+pub const MANIFEST_FILE: &str = "remix-manifest.json";
+
+/// The package an install was assembled from.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct BuildSource {
+    pub is_stable: bool,
+    pub is_x86: bool,
+    pub build_type: String,
+    /// Whether the dxwrapper D3D8 layer was added to an x86 install
+    #[serde(default = "default_dx8_wrapper")]
+    pub dx8_wrapper: bool,
+    pub package_name: String,
+    pub download_url: String,
+    /// Release tag of a stable build
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub release_tag: Option<String>,
+    /// Workflow run and artifact a development build came from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub run_id: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub artifact_id: Option<u64>,
+    /// dxvk-remix commit a development build was made from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub head_sha: Option<String>,
+    /// bridge-remix commit bundled with an x86 development build
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bridge_sha: Option<String>,
+    /// Pull request whose latest run the package came from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pull_request: Option<u64>,
+    /// Package zip installed from disk instead of GitHub
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub local_zip: Option<PathBuf>,
+    /// Debug symbols package added with --with-symbols
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub symbols_url: Option<String>,
+    /// Package the x86 bridge was taken from, when it differs from the runtime's
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bridge: Option<Box<BuildSource>>,
+}
+
+/// Manifests written before D3D detection always had the layer on x86
+fn default_dx8_wrapper() -> bool {
+    true
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Manifest {
+    pub source: BuildSource,
+    pub files: Vec<FileEntry>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct FileEntry {
+    pub path: String,
+    pub sha256: String,
+}
+
+impl Manifest {
+    pub fn load(install_path: &Path) -> Result<Self> {
+        let manifest_path = install_path.join(MANIFEST_FILE);
+        if !manifest_path.exists() {
+            anyhow::bail!(Error::not_found(format!(
+                "{} in {}, was it installed by this downloader?",
+                MANIFEST_FILE,
+                install_path.display()
+            )));
+        }
+        let contents =
+            fs::read_to_string(&manifest_path).map_err(|e| Error::filesystem(&manifest_path, e))?;
+        Ok(serde_json::from_str(&contents).map_err(|e| {
+            Error::invalid(format!(
+                "Could not parse {}: {}",
+                manifest_path.display(),
+                e
+            ))
+        })?)
+    }
+}
+
+/// Hashes every file in a freshly assembled build and records it next to them.
+pub fn write_manifest(final_path: &Path, source: &BuildSource) -> Result<()> {
+    let mut files = Vec::new();
+    for relative in list_files(final_path).map_err(|e| Error::filesystem(final_path, e))? {
+        let key = path_key(&relative);
+        if key == MANIFEST_FILE {
+            continue;
+        }
+        files.push(FileEntry {
+            sha256: sha256_file(&final_path.join(&relative))?,
+            path: key,
+        });
+    }
+
+    let manifest = Manifest {
+        source: source.clone(),
+        files,
+    };
+    let manifest_path = final_path.join(MANIFEST_FILE);
+    fs::write(&manifest_path, serde_json::to_string_pretty(&manifest)?)
+        .map_err(|e| Error::filesystem(&manifest_path, e))?;
+    Ok(())
+}
+
+#[derive(Default)]
+pub struct VerifyReport {
+    pub missing: Vec<String>,
+    pub modified: Vec<String>,
+    /// Edited user config files, which are expected to differ
+    pub user_modified: Vec<String>,
+    pub extra: Vec<String>,
+}
+
+impl VerifyReport {
+    fn broken(&self) -> impl Iterator<Item = &String> {
+        self.missing.iter().chain(self.modified.iter())
+    }
+}
+
+pub fn check_install(install_path: &Path, manifest: &Manifest) -> Result<VerifyReport> {
+    let mut report = VerifyReport::default();
+    for entry in &manifest.files {
+        let file_path = install_path.join(&entry.path);
+        if !file_path.is_file() {
+            report.missing.push(entry.path.clone());
+        } else if sha256_file(&file_path)? != entry.sha256 {
+            if MERGED_CONFIGS.contains(&entry.path.as_str()) {
+                report.user_modified.push(entry.path.clone());
+            } else {
+                report.modified.push(entry.path.clone());
+            }
+        }
+    }
+
+    // A game folder holds the game's own files next to ours, so only look for
+    // extra files in the folders the package created there
+    let is_game_dir = install_path.join(deploy::BACKUP_DIR).exists();
+    let known: HashSet<&str> = manifest.files.iter().map(|f| f.path.as_str()).collect();
+    let owned_dirs: HashSet<&str> = manifest
+        .files
+        .iter()
+        .filter_map(|f| f.path.split_once('/').map(|(dir, _)| dir))
+        .collect();
+    for relative in list_files(install_path).map_err(|e| Error::filesystem(install_path, e))? {
+        let key = path_key(&relative);
+        let top_dir = key.split_once('/').map(|(dir, _)| dir);
+        let in_scope = match top_dir {
+            Some(dir) => owned_dirs.contains(dir),
+            None => !is_game_dir,
+        };
+        if in_scope
+            && !known.contains(key.as_str())
+            && key != MANIFEST_FILE
+            && !MERGED_CONFIGS.contains(&key.as_str())
+        {
+            report.extra.push(key);
+        }
+    }
+    Ok(report)
+}
+
+pub fn verify(install_path: &Path, reporter: &mut dyn Reporter) -> Result<()> {
+    let manifest = Manifest::load(install_path)?;
+    report!(
+        reporter,
+        "{}",
+        format!(
+            "Verifying {} against {}...",
+            install_path.display(),
+            manifest.source.package_name
+        )
+        .cyan()
+    );
+
+    let report = check_install(install_path, &manifest)?;
+    print_report(&report, reporter);
+
+    let broken = report.broken().count();
+    if broken > 0 {
+        anyhow::bail!(Error::integrity(
+            format!("{} of {} files", broken, manifest.files.len()),
+            "are missing or modified, run `repair` to restore them",
+        ));
+    }
+    report!(
+        reporter,
+        "{}",
+        format!("All {} files match the install", manifest.files.len()).green()
+    );
+    Ok(())
+}
+
+fn print_report(report: &VerifyReport, reporter: &mut dyn Reporter) {
+    for path in &report.missing {
+        report!(reporter, "{} {}", "Missing: ".red(), path);
+    }
+    for path in &report.modified {
+        report!(reporter, "{} {}", "Modified:".red(), path);
+    }
+    for path in &report.user_modified {
+        report!(
+            reporter,
+            "{} {} (user settings)",
+            "Edited:  ".yellow(),
+            path
+        );
+    }
+    for path in &report.extra {
+        report!(reporter, "{} {}", "Extra:   ".yellow(), path);
+    }
+}
+
+/// Re-fetches the recorded build and puts back missing or corrupted files,
+/// leaving edited configs and files the install didn't create untouched. The build is
+/// laid out with the rules in `layout_file`, or the usual ones without it.
+pub fn repair(
+    client: &dyn Http,
+    install_path: &Path,
+    layout_file: Option<&Path>,
+    reporter: &mut dyn Reporter,
+) -> Result<()> {
+    let manifest = Manifest::load(install_path)?;
+    let report = check_install(install_path, &manifest)?;
+    print_report(&report, reporter);
+
+    let broken: Vec<&String> = report.broken().collect();
+    if broken.is_empty() {
+        report!(reporter, "{}", "Nothing to repair".green());
+        return Ok(());
+    }
+
+    report!(
+        reporter,
+        "{}",
+        format!(
+            "Re-downloading {} to restore {} files...",
+            manifest.source.package_name,
+            broken.len()
+        )
+        .cyan()
+    );
+    let staging_path = std::env::temp_dir().join(format!("remix-repair-{}", std::process::id()));
+    crate::install::cleanup_existing_directory(&staging_path, reporter)?;
+    let rules = crate::layout::Rules::load(layout_file, reporter)?;
+    let result = restore_from_staging(
+        client,
+        install_path,
+        &manifest,
+        &broken,
+        &staging_path,
+        &rules,
+        reporter,
+    );
+    fs::remove_dir_all(&staging_path).ok();
+    let unrestored = result?;
+
+    if !unrestored.is_empty() {
+        for path in &unrestored {
+            reporter.warning(&format!(
+                "{} no longer matches the installed build upstream",
+                path
+            ));
+        }
+        anyhow::bail!(Error::integrity(
+            format!("{} files", unrestored.len()),
+            "could not be restored, they no longer match the installed build upstream",
+        ));
+    }
+    report!(
+        reporter,
+        "{}",
+        format!("Restored {} files", broken.len()).green().bold()
+    );
+    Ok(())
+}
+
+fn restore_from_staging(
+    client: &dyn Http,
+    install_path: &Path,
+    manifest: &Manifest,
+    broken: &[&String],
+    staging_path: &Path,
+    rules: &crate::layout::Rules,
+    reporter: &mut dyn Reporter,
+) -> Result<Vec<String>> {
+    let mut downloader = crate::download::Downloader::new(client, DownloadLog::default(), reporter);
+    crate::install::assemble_build(&mut downloader, &manifest.source, staging_path, rules)?;
+
+    let mut unrestored = Vec::new();
+    for key in broken {
+        let entry = manifest
+            .files
+            .iter()
+            .find(|f| &f.path == *key)
+            .context("File missing from manifest")?;
+        let staged_path = staging_path.join(&entry.path);
+        if !staged_path.is_file() || sha256_file(&staged_path)? != entry.sha256 {
+            unrestored.push(entry.path.clone());
+            continue;
+        }
+
+        let dest_path: PathBuf = install_path.join(&entry.path);
+        if let Some(parent) = dest_path.parent() {
+            fs::create_dir_all(parent).map_err(|e| Error::filesystem(parent, e))?;
+        }
+        fs::copy(&staged_path, &dest_path).map_err(|e| Error::filesystem(&dest_path, e))?;
+        report!(reporter, "{}", format!("Restored {}", entry.path).cyan());
+    }
+    Ok(unrestored)
+}
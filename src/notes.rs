@@ -0,0 +1,192 @@
+//! What a build contains: the release notes of a stable build, or the commits a dev build
+//! adds over the one installed, shown before anything is downloaded.
+
+use anyhow::{Context, Result};
+use colored::*;
+use serde_json::Value;
+
+use crate::catalog::{self, Repo};
+use crate::http::{self, Http};
+use crate::manifest::BuildSource;
+use crate::progress::Reporter;
+
+/// Commits listed before the rest are summarized as a count
+const MAX_COMMITS: usize = 30;
+
+/// Prints the notes for the build about to be installed. Failing to fetch them only warns,
+/// as they don't affect the install.
+pub fn show(
+    client: &dyn Http,
+    source: &BuildSource,
+    previous: Option<&BuildSource>,
+    reporter: &mut dyn Reporter,
+) {
+    if let Err(e) = print_notes(client, source, previous, reporter) {
+        reporter.warning(&format!("Could not fetch what's in this build: {:#}", e));
+    }
+}
+
+fn print_notes(
+    client: &dyn Http,
+    source: &BuildSource,
+    previous: Option<&BuildSource>,
+    reporter: &mut dyn Reporter,
+) -> Result<()> {
+    if let Some(tag) = &source.release_tag {
+        if previous.and_then(|previous| previous.release_tag.as_ref()) == Some(tag) {
+            report!(
+                reporter,
+                "{}",
+                format!("{} is already installed", tag).cyan()
+            );
+        }
+        return print_release_notes(client, tag, reporter);
+    }
+
+    // Dev builds are compared with the installed one when it was a dev build too
+    let previous = previous.filter(|previous| previous.release_tag.is_none());
+    if let Some(head) = &source.head_sha {
+        let base = previous.and_then(|previous| previous.head_sha.as_deref());
+        print_commits(client, Repo::Dxvk, base, head, reporter)?;
+    }
+    if let Some(head) = bridge_sha(source) {
+        let base = previous.and_then(bridge_sha);
+        print_commits(client, Repo::Bridge, base, head, reporter)?;
+    }
+    Ok(())
+}
+
+/// The bridge-remix commit an install's bridge came from, if it's known.
+fn bridge_sha(source: &BuildSource) -> Option<&str> {
+    match &source.bridge {
+        Some(bridge) => bridge.bridge_sha.as_deref(),
+        None => source.bridge_sha.as_deref(),
+    }
+}
+
+fn print_release_notes(client: &dyn Http, tag: &str, reporter: &mut dyn Reporter) -> Result<()> {
+    let release_url = format!(
+        "{}/repos/{}/releases/tags/{}",
+        catalog::get().hosts.api,
+        catalog::get().repo(Repo::Rtx),
+        tag
+    );
+    let release = http::get_json(client, &release_url)?;
+    report!(
+        reporter,
+        "\n{}",
+        format!("Release notes for {}", tag).green().bold()
+    );
+    match release["body"].as_str().map(str::trim) {
+        Some(body) if !body.is_empty() => {
+            for line in body.lines() {
+                report!(reporter, "  {}", line);
+            }
+        }
+        _ => report!(reporter, "  (none)"),
+    }
+    report!(reporter);
+    Ok(())
+}
+
+/// Lists the commits from `base` to `head`, or just `head` when nothing to compare with.
+fn print_commits(
+    client: &dyn Http,
+    repo: Repo,
+    base: Option<&str>,
+    head: &str,
+    reporter: &mut dyn Reporter,
+) -> Result<()> {
+    let repo_name = catalog::get().repo(repo);
+    let Some(base) = base else {
+        let commit_url = format!(
+            "{}/repos/{}/commits/{}",
+            catalog::get().hosts.api,
+            repo_name,
+            head
+        );
+        let commit = http::get_json(client, &commit_url)?;
+        report!(
+            reporter,
+            "\n{}",
+            format!("{} commit", repo_name).green().bold()
+        );
+        print_commit(&commit, reporter);
+        return Ok(());
+    };
+    if base == head {
+        report!(
+            reporter,
+            "{}",
+            format!(
+                "{} is unchanged since the installed build ({})",
+                repo_name,
+                short(head)
+            )
+            .cyan()
+        );
+        return Ok(());
+    }
+
+    let compare_url = format!(
+        "{}/repos/{}/compare/{}...{}",
+        catalog::get().hosts.api,
+        repo_name,
+        base,
+        head
+    );
+    let comparison = http::get_json(client, &compare_url)?;
+    let commits = comparison["commits"]
+        .as_array()
+        .context("Unexpected response comparing commits")?;
+
+    report!(
+        reporter,
+        "\n{}",
+        format!(
+            "{} changes since the installed build ({}..{})",
+            repo_name,
+            short(base),
+            short(head)
+        )
+        .green()
+        .bold()
+    );
+    if comparison["status"] == "behind" {
+        report!(
+            reporter,
+            "{}",
+            "  This build is older than the installed one".yellow()
+        );
+    }
+    // The newest commits are the most relevant, and the API lists them last
+    let skipped = commits.len().saturating_sub(MAX_COMMITS);
+    for commit in commits.iter().skip(skipped).rev() {
+        print_commit(commit, reporter);
+    }
+    let total = comparison["total_commits"]
+        .as_u64()
+        .unwrap_or(commits.len() as u64);
+    let hidden = total.saturating_sub((commits.len() - skipped) as u64);
+    if hidden > 0 {
+        report!(reporter, "  ... and {} older commits", hidden);
+    }
+    Ok(())
+}
+
+fn print_commit(commit: &Value, reporter: &mut dyn Reporter) {
+    let message = commit["commit"]["message"].as_str().unwrap_or_default();
+    report!(
+        reporter,
+        "  {} {} ({})",
+        short(commit["sha"].as_str().unwrap_or_default()).yellow(),
+        message.lines().next().unwrap_or_default(),
+        commit["commit"]["author"]["name"]
+            .as_str()
+            .unwrap_or("unknown")
+    );
+}
+
+fn short(sha: &str) -> &str {
+    sha.get(..7).unwrap_or(sha)
+}
@@ -0,0 +1,110 @@
+//! Where the downloader's output goes. By default status messages are printed for people;
+//! with `--output json` stdout carries one JSON event per line for launchers to parse and
+//! the human-readable messages move to stderr.
+
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use clap::ValueEnum;
+use colored::*;
+use serde_json::{json, Value};
+
+use crate::error::Error;
+
+static JSON: AtomicBool = AtomicBool::new(false);
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    Human,
+    Json,
+}
+
+/// Exit codes, kept stable for scripts and launchers
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Exit {
+    Success = 0,
+    /// Anything not covered below
+    Failure = 1,
+    /// Invalid arguments, input or configuration; also what clap uses for usage errors
+    UserError = 2,
+    /// GitHub or a download couldn't be reached
+    Network = 3,
+    /// A download or install doesn't match its recorded hash
+    Integrity = 4,
+    /// `install --check` found a newer build than the installed one
+    UpdateAvailable = 5,
+}
+
+/// The exit code for an error, from the first cause that determines one.
+pub fn exit_for(error: &anyhow::Error) -> Exit {
+    for cause in error.chain() {
+        if let Some(error) = cause.downcast_ref::<Error>() {
+            return error.exit();
+        }
+        if cause.downcast_ref::<reqwest::Error>().is_some() {
+            return Exit::Network;
+        }
+    }
+    Exit::Failure
+}
+
+pub fn set_format(format: Format) {
+    JSON.store(format == Format::Json, Ordering::Relaxed);
+}
+
+pub fn is_json() -> bool {
+    JSON.load(Ordering::Relaxed)
+}
+
+/// Prints a status message for people: on stdout, or on stderr when stdout carries events.
+#[macro_export]
+macro_rules! status {
+    () => {
+        if $crate::output::is_json() {
+            eprintln!()
+        } else {
+            println!()
+        }
+    };
+    ($($arg:tt)*) => {
+        if $crate::output::is_json() {
+            eprintln!($($arg)*)
+        } else {
+            println!($($arg)*)
+        }
+    };
+}
+
+/// Sends a status message to a `Reporter`, formatted like `format!`.
+#[macro_export]
+macro_rules! report {
+    ($reporter:expr) => {
+        $reporter.message("")
+    };
+    ($reporter:expr, $($arg:tt)*) => {
+        $reporter.message(&format!($($arg)*))
+    };
+}
+
+/// Prints a warning on stderr, also emitting it as an event in JSON mode.
+pub fn warn(message: impl fmt::Display) {
+    let message = message.to_string();
+    eprintln!("{}", format!("Warning: {}", message).yellow());
+    event("warning", json!({ "message": message }));
+}
+
+/// Emits an event on stdout in JSON mode; does nothing otherwise.
+pub fn event(kind: &str, fields: Value) {
+    if !is_json() {
+        return;
+    }
+    // Written by hand so the event name always comes first
+    let mut line = format!("{{\"event\":{}", Value::from(kind));
+    if let Value::Object(fields) = fields {
+        for (key, value) in fields {
+            line.push_str(&format!(",{}:{}", Value::from(key), value));
+        }
+    }
+    line.push('}');
+    println!("{}", line);
+}
@@ -0,0 +1,526 @@
+//! Minimal PE/COFF reader for inspecting game executables.
+
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+
+use crate::error::Error;
+
+const IMAGE_FILE_MACHINE_I386: u16 = 0x014c;
+const IMAGE_FILE_MACHINE_AMD64: u16 = 0x8664;
+const IMAGE_NT_OPTIONAL_HDR64_MAGIC: u16 = 0x20b;
+const IMAGE_DIRECTORY_ENTRY_IMPORT: usize = 1;
+const IMAGE_DIRECTORY_ENTRY_RESOURCE: usize = 2;
+const IMAGE_DIRECTORY_ENTRY_DELAY_IMPORT: usize = 13;
+const RT_VERSION: u32 = 16;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Machine {
+    X86,
+    X64,
+    Other(u16),
+}
+
+impl Machine {
+    pub fn describe(self) -> String {
+        match self {
+            Machine::X86 => "32-bit (x86)".to_string(),
+            Machine::X64 => "64-bit (x64)".to_string(),
+            Machine::Other(machine) => format!("unsupported machine type 0x{:04x}", machine),
+        }
+    }
+}
+
+pub struct PeFile {
+    data: Vec<u8>,
+    pe_offset: usize,
+    sections: Vec<Section>,
+}
+
+struct Section {
+    virtual_address: u32,
+    virtual_size: u32,
+    raw_size: u32,
+    raw_offset: u32,
+}
+
+impl PeFile {
+    pub fn open(path: &Path) -> Result<Self> {
+        let data = fs::read(path).map_err(|e| Error::filesystem(path, e))?;
+        Self::parse(data).with_context(|| format!("{} is not a Windows executable", path.display()))
+    }
+
+    pub fn parse(data: Vec<u8>) -> Result<Self> {
+        if data.get(..2) != Some(b"MZ") {
+            anyhow::bail!("missing MZ header");
+        }
+        let pe_offset = read_u32(&data, 0x3c).context("truncated DOS header")? as usize;
+        if data.get(pe_offset..pe_offset + 4) != Some(b"PE\0\0") {
+            anyhow::bail!("missing PE signature");
+        }
+
+        let section_count = read_u16(&data, pe_offset + 6).context("truncated COFF header")?;
+        let optional_size = read_u16(&data, pe_offset + 20).context("truncated COFF header")?;
+        let section_table = pe_offset + 24 + optional_size as usize;
+        let sections = (0..section_count as usize)
+            .map_while(|i| {
+                let header = section_table + i * 40;
+                Some(Section {
+                    virtual_size: read_u32(&data, header + 8)?,
+                    virtual_address: read_u32(&data, header + 12)?,
+                    raw_size: read_u32(&data, header + 16)?,
+                    raw_offset: read_u32(&data, header + 20)?,
+                })
+            })
+            .collect();
+
+        Ok(Self {
+            data,
+            pe_offset,
+            sections,
+        })
+    }
+
+    pub fn machine(&self) -> Machine {
+        match read_u16(&self.data, self.pe_offset + 4) {
+            Some(IMAGE_FILE_MACHINE_I386) => Machine::X86,
+            Some(IMAGE_FILE_MACHINE_AMD64) => Machine::X64,
+            Some(other) => Machine::Other(other),
+            None => Machine::Other(0),
+        }
+    }
+
+    /// Names of every DLL the image links against, including delay-loaded ones, lowercased.
+    pub fn imported_dlls(&self) -> Vec<String> {
+        let mut names = Vec::new();
+
+        if let Some(table) = self.directory_offset(IMAGE_DIRECTORY_ENTRY_IMPORT) {
+            // IMAGE_IMPORT_DESCRIPTOR entries are 20 bytes with the name RVA at +12
+            for descriptor in (table..).step_by(20) {
+                match read_u32(&self.data, descriptor + 12) {
+                    Some(0) | None => break,
+                    Some(name_rva) => names.extend(self.read_name(name_rva as u64)),
+                }
+            }
+        }
+
+        if let Some(table) = self.directory_offset(IMAGE_DIRECTORY_ENTRY_DELAY_IMPORT) {
+            // Delay-load descriptors are 32 bytes with the name at +4; old linkers
+            // stored a virtual address there instead of an RVA
+            for descriptor in (table..).step_by(32) {
+                let (Some(attributes), Some(name)) = (
+                    read_u32(&self.data, descriptor),
+                    read_u32(&self.data, descriptor + 4),
+                ) else {
+                    break;
+                };
+                if name == 0 {
+                    break;
+                }
+                let name_rva = if attributes & 1 == 0 {
+                    (name as u64).wrapping_sub(self.image_base())
+                } else {
+                    name as u64
+                };
+                names.extend(self.read_name(name_rva));
+            }
+        }
+
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    /// Whether the raw image contains a byte string, case-insensitively.
+    pub fn contains_marker(&self, marker: &str) -> bool {
+        let marker = marker.as_bytes();
+        self.data
+            .windows(marker.len())
+            .any(|window| window.eq_ignore_ascii_case(marker))
+    }
+
+    /// Key/value pairs from the version resource's string table, such as
+    /// `ProductName` or `CompanyName`. Empty when the image has no version info.
+    pub fn version_strings(&self) -> Vec<(String, String)> {
+        let mut strings = Vec::new();
+        if let Some(blob) = self.version_resource() {
+            walk_version_block(blob, 0, 0, &mut strings);
+        }
+        strings
+    }
+
+    fn version_resource(&self) -> Option<&[u8]> {
+        let root = self.directory_offset(IMAGE_DIRECTORY_ENTRY_RESOURCE)?;
+        // Resources are a type -> name -> language tree; take the first version entry
+        let names = self.resource_child(root, root, Some(RT_VERSION))?;
+        let languages = self.resource_child(root, names, None)?;
+        let data_entry = self.resource_child(root, languages, None)?;
+
+        let rva = read_u32(&self.data, data_entry)?;
+        let size = read_u32(&self.data, data_entry + 4)? as usize;
+        let start = self.rva_to_offset(rva as u64)?;
+        self.data.get(start..start + size)
+    }
+
+    fn resource_child(&self, root: usize, directory: usize, id: Option<u32>) -> Option<usize> {
+        let named = read_u16(&self.data, directory + 12)? as usize;
+        let ids = read_u16(&self.data, directory + 14)? as usize;
+        (0..named + ids).find_map(|i| {
+            let entry = directory + 16 + i * 8;
+            let name = read_u32(&self.data, entry)?;
+            let target = read_u32(&self.data, entry + 4)?;
+            id.is_none_or(|id| name == id)
+                .then_some(root + (target & 0x7fff_ffff) as usize)
+        })
+    }
+
+    fn optional_header(&self) -> usize {
+        self.pe_offset + 24
+    }
+
+    fn is_pe32_plus(&self) -> bool {
+        read_u16(&self.data, self.optional_header()) == Some(IMAGE_NT_OPTIONAL_HDR64_MAGIC)
+    }
+
+    fn image_base(&self) -> u64 {
+        let optional = self.optional_header();
+        if self.is_pe32_plus() {
+            read_u64(&self.data, optional + 24).unwrap_or(0)
+        } else {
+            read_u32(&self.data, optional + 28).unwrap_or(0) as u64
+        }
+    }
+
+    /// File offset of a data directory, if the image has one.
+    fn directory_offset(&self, index: usize) -> Option<usize> {
+        let optional = self.optional_header();
+        let (count_offset, directories) = if self.is_pe32_plus() {
+            (optional + 108, optional + 112)
+        } else {
+            (optional + 92, optional + 96)
+        };
+        if index >= read_u32(&self.data, count_offset)? as usize {
+            return None;
+        }
+        let rva = read_u32(&self.data, directories + index * 8)?;
+        if rva == 0 {
+            return None;
+        }
+        self.rva_to_offset(rva as u64)
+    }
+
+    fn rva_to_offset(&self, rva: u64) -> Option<usize> {
+        let section = self.sections.iter().find(|section| {
+            let start = section.virtual_address as u64;
+            rva >= start && rva < start + section.virtual_size.max(section.raw_size) as u64
+        })?;
+        let offset = rva - section.virtual_address as u64 + section.raw_offset as u64;
+        (offset < self.data.len() as u64).then_some(offset as usize)
+    }
+
+    fn read_name(&self, rva: u64) -> Option<String> {
+        let start = self.rva_to_offset(rva)?;
+        let bytes = &self.data[start..];
+        let end = bytes.iter().position(|&b| b == 0)?;
+        Some(String::from_utf8_lossy(&bytes[..end]).to_ascii_lowercase())
+    }
+}
+
+/// Walks a VS_VERSIONINFO block, collecting the strings from its StringFileInfo.
+/// Depth 0 is the root, 1 StringFileInfo, 2 a StringTable and 3 a single String.
+fn walk_version_block(
+    blob: &[u8],
+    start: usize,
+    depth: usize,
+    strings: &mut Vec<(String, String)>,
+) -> Option<usize> {
+    let length = read_u16(blob, start)? as usize;
+    let value_length = read_u16(blob, start + 2)? as usize;
+    let is_text = read_u16(blob, start + 4)? == 1;
+    let (key, key_end) = read_utf16z(blob, start + 6)?;
+    let value_start = align4(key_end);
+    let end = (start + length).min(blob.len());
+
+    if depth == 3 {
+        if value_length > 0 {
+            let (value, _) = read_utf16z(&blob[..end], value_start)?;
+            strings.push((key, value));
+        }
+        return Some(length);
+    }
+    if depth == 1 && key != "StringFileInfo" {
+        return Some(length);
+    }
+
+    let value_size = if is_text {
+        value_length * 2
+    } else {
+        value_length
+    };
+    let mut child = align4(value_start + value_size);
+    while child + 6 < end {
+        let child_length = walk_version_block(blob, child, depth + 1, strings)?;
+        if child_length == 0 {
+            break;
+        }
+        child = align4(child + child_length);
+    }
+    Some(length)
+}
+
+fn read_utf16z(data: &[u8], offset: usize) -> Option<(String, usize)> {
+    let mut units = Vec::new();
+    let mut position = offset;
+    loop {
+        let unit = read_u16(data, position)?;
+        position += 2;
+        if unit == 0 {
+            break;
+        }
+        units.push(unit);
+    }
+    Some((String::from_utf16_lossy(&units), position))
+}
+
+fn align4(offset: usize) -> usize {
+    (offset + 3) & !3
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Option<u16> {
+    let bytes = data.get(offset..offset + 2)?;
+    Some(u16::from_le_bytes([bytes[0], bytes[1]]))
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    let bytes = data.get(offset..offset + 4)?;
+    Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+fn read_u64(data: &[u8], offset: usize) -> Option<u64> {
+    let bytes = data.get(offset..offset + 8)?;
+    Some(u64::from_le_bytes(bytes.try_into().ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECTION_RVA: u32 = 0x1000;
+    const SECTION_OFFSET: usize = 0x400;
+    const IMAGE_BASE: u32 = 0x40_0000;
+
+    /// An image with one section holding `data` at `SECTION_RVA`, and the given data
+    /// directories pointing into it.
+    fn image(machine: u16, pe32_plus: bool, directories: &[(usize, u32)], data: &[u8]) -> Vec<u8> {
+        let optional_size = if pe32_plus { 240 } else { 224 };
+        let mut image = vec![0; SECTION_OFFSET];
+        image[..2].copy_from_slice(b"MZ");
+        put_u32(&mut image, 0x3c, 0x40);
+        image[0x40..0x44].copy_from_slice(b"PE\0\0");
+        put_u16(&mut image, 0x44, machine);
+        put_u16(&mut image, 0x46, 1);
+        put_u16(&mut image, 0x54, optional_size);
+
+        let optional = 0x58;
+        let directory_table = if pe32_plus {
+            put_u16(&mut image, optional, IMAGE_NT_OPTIONAL_HDR64_MAGIC);
+            image[optional + 24..optional + 32].copy_from_slice(&(IMAGE_BASE as u64).to_le_bytes());
+            put_u32(&mut image, optional + 108, 16);
+            optional + 112
+        } else {
+            put_u16(&mut image, optional, 0x10b);
+            put_u32(&mut image, optional + 28, IMAGE_BASE);
+            put_u32(&mut image, optional + 92, 16);
+            optional + 96
+        };
+        for &(index, offset) in directories {
+            put_u32(
+                &mut image,
+                directory_table + index * 8,
+                SECTION_RVA + offset,
+            );
+        }
+
+        let section = optional + optional_size as usize;
+        put_u32(&mut image, section + 8, data.len() as u32);
+        put_u32(&mut image, section + 12, SECTION_RVA);
+        put_u32(&mut image, section + 16, data.len() as u32);
+        put_u32(&mut image, section + 20, SECTION_OFFSET as u32);
+        image.extend_from_slice(data);
+        image
+    }
+
+    fn put_u16(data: &mut [u8], offset: usize, value: u16) {
+        data[offset..offset + 2].copy_from_slice(&value.to_le_bytes());
+    }
+
+    fn put_u32(data: &mut [u8], offset: usize, value: u32) {
+        data[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+    }
+
+    /// Section data with `name` written at offset 0x100, for import descriptors to point at.
+    fn with_name(mut data: Vec<u8>, name: &str) -> Vec<u8> {
+        data.resize(0x100, 0);
+        data.extend_from_slice(name.as_bytes());
+        data.push(0);
+        data
+    }
+
+    #[test]
+    fn reads_the_machine_type() {
+        let x86 = PeFile::parse(image(IMAGE_FILE_MACHINE_I386, false, &[], &[])).unwrap();
+        assert!(x86.machine() == Machine::X86);
+        let x64 = PeFile::parse(image(IMAGE_FILE_MACHINE_AMD64, true, &[], &[])).unwrap();
+        assert!(x64.machine() == Machine::X64);
+        let arm64 = PeFile::parse(image(0xaa64, true, &[], &[])).unwrap();
+        assert!(arm64.machine() == Machine::Other(0xaa64));
+    }
+
+    #[test]
+    fn lists_imported_dlls() {
+        // One IMAGE_IMPORT_DESCRIPTOR naming the DLL, then an empty one ending the table
+        let mut descriptors = vec![0; 40];
+        put_u32(&mut descriptors, 12, SECTION_RVA + 0x100);
+        let data = with_name(descriptors, "D3D8.dll");
+        let pe = PeFile::parse(image(
+            IMAGE_FILE_MACHINE_I386,
+            false,
+            &[(IMAGE_DIRECTORY_ENTRY_IMPORT, 0)],
+            &data,
+        ))
+        .unwrap();
+        assert_eq!(pe.imported_dlls(), ["d3d8.dll"]);
+    }
+
+    #[test]
+    fn lists_delay_loaded_dlls() {
+        // A PE32+ descriptor naming the DLL by RVA
+        let mut descriptors = vec![0; 64];
+        put_u32(&mut descriptors, 0, 1);
+        put_u32(&mut descriptors, 4, SECTION_RVA + 0x100);
+        let data = with_name(descriptors, "d3d9.dll");
+        let pe = PeFile::parse(image(
+            IMAGE_FILE_MACHINE_AMD64,
+            true,
+            &[(IMAGE_DIRECTORY_ENTRY_DELAY_IMPORT, 0)],
+            &data,
+        ))
+        .unwrap();
+        assert_eq!(pe.imported_dlls(), ["d3d9.dll"]);
+
+        // Old linkers stored a virtual address instead
+        let mut descriptors = vec![0; 64];
+        put_u32(&mut descriptors, 4, IMAGE_BASE + SECTION_RVA + 0x100);
+        let data = with_name(descriptors, "d3d8.dll");
+        let pe = PeFile::parse(image(
+            IMAGE_FILE_MACHINE_I386,
+            false,
+            &[(IMAGE_DIRECTORY_ENTRY_DELAY_IMPORT, 0)],
+            &data,
+        ))
+        .unwrap();
+        assert_eq!(pe.imported_dlls(), ["d3d8.dll"]);
+    }
+
+    /// A VS_VERSIONINFO style block with a text value or children.
+    fn version_block(key: &str, value: Option<&str>, children: &[u8]) -> Vec<u8> {
+        fn utf16z(text: &str) -> Vec<u8> {
+            text.encode_utf16()
+                .chain([0])
+                .flat_map(u16::to_le_bytes)
+                .collect()
+        }
+        let mut block = vec![0; 6];
+        put_u16(&mut block, 4, 1);
+        block.extend(utf16z(key));
+        block.resize(align4(block.len()), 0);
+        match value {
+            Some(value) => {
+                put_u16(&mut block, 2, value.encode_utf16().count() as u16 + 1);
+                block.extend(utf16z(value));
+                block.resize(align4(block.len()), 0);
+            }
+            None => block.extend_from_slice(children),
+        }
+        let length = block.len() as u16;
+        put_u16(&mut block, 0, length);
+        block
+    }
+
+    #[test]
+    fn reads_version_strings() {
+        let strings = [
+            version_block("ProductName", Some("ReShade"), &[]),
+            version_block("CompanyName", Some("crosire"), &[]),
+        ]
+        .concat();
+        let table = version_block("040904b0", None, &strings);
+        let string_file_info = version_block("StringFileInfo", None, &table);
+        let version_info = version_block("VS_VERSION_INFO", None, &string_file_info);
+
+        // Type, name and language directories of one entry each, then the data entry
+        let mut resources = vec![0; 0x48];
+        for (directory, id, target) in [
+            (0x00, RT_VERSION, 0x8000_0018),
+            (0x18, 1, 0x8000_0030),
+            (0x30, 0x409, 0x48),
+        ] {
+            put_u16(&mut resources, directory + 14, 1);
+            put_u32(&mut resources, directory + 16, id);
+            put_u32(&mut resources, directory + 20, target);
+        }
+        resources.resize(0x50, 0);
+        put_u32(&mut resources, 0x48, SECTION_RVA + 0x50);
+        put_u32(&mut resources, 0x4c, version_info.len() as u32);
+        resources.extend(version_info);
+
+        let pe = PeFile::parse(image(
+            IMAGE_FILE_MACHINE_I386,
+            false,
+            &[(IMAGE_DIRECTORY_ENTRY_RESOURCE, 0)],
+            &resources,
+        ))
+        .unwrap();
+        assert_eq!(
+            pe.version_strings(),
+            [
+                ("ProductName".to_string(), "ReShade".to_string()),
+                ("CompanyName".to_string(), "crosire".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_truncated_headers() {
+        assert!(PeFile::parse(Vec::new()).is_err());
+        assert!(PeFile::parse(b"MZ".to_vec()).is_err());
+
+        let full = image(IMAGE_FILE_MACHINE_I386, false, &[], &[]);
+        // Cut off inside the COFF header, right after the PE signature
+        assert!(PeFile::parse(full[..0x46].to_vec()).is_err());
+
+        let mut far_signature = full.clone();
+        put_u32(&mut far_signature, 0x3c, u32::MAX);
+        assert!(PeFile::parse(far_signature).is_err());
+    }
+
+    #[test]
+    fn survives_a_bogus_section_count() {
+        let mut data = image(
+            IMAGE_FILE_MACHINE_I386,
+            false,
+            &[
+                (IMAGE_DIRECTORY_ENTRY_IMPORT, 0),
+                (IMAGE_DIRECTORY_ENTRY_RESOURCE, 0),
+            ],
+            &[0xff; 16],
+        );
+        put_u16(&mut data, 0x46, u16::MAX);
+        let pe = PeFile::parse(data).unwrap();
+        assert!(pe.machine() == Machine::X86);
+        // The directories point into garbage, which must only mean finding nothing
+        let _ = pe.imported_dlls();
+        let _ = pe.version_strings();
+    }
+}
@@ -0,0 +1,292 @@
+//! What an install does, recorded while a build is laid out so `--dry-run` can show it.
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use colored::*;
+use serde::Serialize;
+
+use crate::builds::format_size;
+use crate::config;
+use crate::conflicts::{self, Conflict};
+use crate::deploy::{self, InstallRecord};
+use crate::error::Error;
+use crate::files::{list_files, path_key};
+use crate::manifest;
+use crate::progress::Reporter;
+
+#[derive(Default)]
+pub struct Plan {
+    /// URL, or path of a local zip, of everything fetched, with its size in bytes
+    pub downloads: Vec<(String, u64)>,
+    /// Files extracted from each archive, by archive name
+    pub extracted: Vec<(String, Vec<String>)>,
+    /// Files removed, renamed, moved or placed after extraction, in order
+    pub changes: Vec<String>,
+}
+
+/// A plan as a whole, for `Reporter::planned`
+#[derive(Serialize)]
+pub struct Summary {
+    pub downloads: Vec<Download>,
+    /// Total size of the downloads in bytes
+    pub download_size: u64,
+    pub extracted: Vec<Extracted>,
+    pub changes: Vec<String>,
+    /// The game folder, or the remix folder without one
+    pub target: PathBuf,
+    pub target_exists: bool,
+    pub effects: Vec<Effect>,
+    /// Wrappers in the game folder that stop Remix from loading
+    pub conflicts: Vec<Conflict>,
+}
+
+#[derive(Serialize)]
+pub struct Download {
+    /// URL, or path of a local zip
+    pub url: String,
+    pub size: u64,
+}
+
+#[derive(Serialize)]
+pub struct Extracted {
+    pub archive: String,
+    pub files: Vec<String>,
+}
+
+/// What installing the build does to one file of the folder it goes into
+#[derive(Serialize)]
+pub struct Effect {
+    pub action: Action,
+    pub path: String,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Action {
+    /// A file that isn't there yet
+    Add,
+    /// A config from the previous install, merged with the user's settings
+    Merge,
+    /// A file from the previous install
+    Replace,
+    /// A file that isn't the downloader's, backed up first
+    Overwrite,
+    /// A config the build no longer has, left with the user's settings
+    Keep,
+    /// A file from the previous install the build no longer has
+    Remove,
+}
+
+impl Plan {
+    pub fn change(&mut self, change: String) {
+        self.changes.push(change);
+    }
+
+    /// Shows the plan for a build laid out in `staged`, and what installing it would do
+    /// to the game folder, or to the remix folder without one, and returns it.
+    pub fn report(
+        &self,
+        staged: &Path,
+        remix_path: &Path,
+        game_dir: Option<&Path>,
+        reporter: &mut dyn Reporter,
+    ) -> Result<Summary> {
+        report!(reporter, "\n{}", "Downloads".green().bold());
+        let total: u64 = self.downloads.iter().map(|(_, size)| size).sum();
+        for (url, size) in &self.downloads {
+            report!(reporter, "  {:>10}  {}", format_size(*size), url);
+        }
+        report!(reporter, "  {:>10}  total", format_size(total));
+
+        for (archive, files) in &self.extracted {
+            report!(
+                reporter,
+                "\n{}",
+                format!("Extracted from {} ({} files)", archive, files.len())
+                    .green()
+                    .bold()
+            );
+            for file in files {
+                report!(reporter, "  {}", file);
+            }
+        }
+
+        report!(reporter, "\n{}", "Changes after extraction".green().bold());
+        if self.changes.is_empty() {
+            report!(reporter, "  none");
+        }
+        for change in &self.changes {
+            report!(reporter, "  {}", change);
+        }
+
+        let package: Vec<String> = list_files(staged)
+            .map_err(|e| Error::filesystem(staged, e))?
+            .iter()
+            .map(|relative| path_key(relative))
+            .filter(|key| key != manifest::MANIFEST_FILE)
+            .collect();
+        let target = game_dir.unwrap_or(remix_path);
+        let target_exists = target.is_dir();
+        let mut found = Vec::new();
+        let effects = match game_dir {
+            Some(game_dir) => {
+                report!(
+                    reporter,
+                    "\n{}",
+                    format!("Installing into {}", game_dir.display())
+                        .green()
+                        .bold()
+                );
+                if target_exists {
+                    found = conflicts::scan(game_dir)?;
+                    if !found.is_empty() {
+                        conflicts::print_conflicts(&found, reporter);
+                    }
+                    game_dir_effects(&package, game_dir)?
+                } else {
+                    report!(reporter, "  {}", "The game folder does not exist".yellow());
+                    Vec::new()
+                }
+            }
+            None => {
+                report!(
+                    reporter,
+                    "\n{}",
+                    format!("Replacing {}", remix_path.display()).green().bold()
+                );
+                remix_effects(&package, remix_path)?
+            }
+        };
+        if target_exists || game_dir.is_none() {
+            report_effects(&effects, reporter);
+        }
+
+        Ok(Summary {
+            downloads: self
+                .downloads
+                .iter()
+                .map(|(url, size)| Download {
+                    url: url.clone(),
+                    size: *size,
+                })
+                .collect(),
+            download_size: total,
+            extracted: self
+                .extracted
+                .iter()
+                .map(|(archive, files)| Extracted {
+                    archive: archive.clone(),
+                    files: files.clone(),
+                })
+                .collect(),
+            changes: self.changes.clone(),
+            target: target.to_path_buf(),
+            target_exists,
+            effects,
+            conflicts: found,
+        })
+    }
+}
+
+fn game_dir_effects(package: &[String], game_dir: &Path) -> Result<Vec<Effect>> {
+    let previous: HashSet<String> = InstallRecord::load(game_dir)?
+        .map(|record| record.installed.into_iter().collect())
+        .unwrap_or_default();
+    let mut effects = Vec::new();
+    for key in package {
+        let action = if !game_dir.join(key).exists() {
+            Action::Add
+        } else if config::MERGED_CONFIGS.contains(&key.as_str()) && previous.contains(key) {
+            Action::Merge
+        } else if previous.contains(key) {
+            Action::Replace
+        } else {
+            Action::Overwrite
+        };
+        effects.push(Effect {
+            action,
+            path: key.clone(),
+        });
+    }
+    let mut stale: Vec<&String> = previous
+        .iter()
+        .filter(|key| !package.contains(key))
+        .collect();
+    stale.sort();
+    for key in stale {
+        let action = if config::MERGED_CONFIGS.contains(&key.as_str()) {
+            Action::Keep
+        } else {
+            Action::Remove
+        };
+        effects.push(Effect {
+            action,
+            path: key.clone(),
+        });
+    }
+    Ok(effects)
+}
+
+fn remix_effects(package: &[String], remix_path: &Path) -> Result<Vec<Effect>> {
+    let existing: Vec<String> = if remix_path.is_dir() {
+        list_files(remix_path)
+            .map_err(|e| Error::filesystem(remix_path, e))?
+            .iter()
+            .map(|relative| path_key(relative))
+            .collect()
+    } else {
+        Vec::new()
+    };
+    let mut effects = Vec::new();
+    for key in &existing {
+        let action = if config::MERGED_CONFIGS.contains(&key.as_str()) && package.contains(key) {
+            Action::Merge
+        } else if package.contains(key) {
+            Action::Replace
+        } else {
+            Action::Remove
+        };
+        effects.push(Effect {
+            action,
+            path: key.clone(),
+        });
+    }
+    for key in package.iter().filter(|key| !existing.contains(key)) {
+        effects.push(Effect {
+            action: Action::Add,
+            path: key.clone(),
+        });
+    }
+    Ok(effects)
+}
+
+/// Lists every effect but additions, which are only counted.
+fn report_effects(effects: &[Effect], reporter: &mut dyn Reporter) {
+    let mut added = 0;
+    for Effect { action, path } in effects {
+        match action {
+            Action::Add => added += 1,
+            Action::Merge => report!(reporter, "  merge settings into {}", path),
+            Action::Replace => report!(reporter, "  replace {}", path),
+            Action::Overwrite => report!(
+                reporter,
+                "  overwrite {} (backed up to {})",
+                path,
+                deploy::BACKUP_DIR
+            ),
+            Action::Keep => report!(
+                reporter,
+                "  keep {}, left from the previous install with your settings",
+                path
+            ),
+            Action::Remove => report!(
+                reporter,
+                "  remove {}, left from the previous install",
+                path
+            ),
+        }
+    }
+    report!(reporter, "  add {} new files", added);
+}
@@ -0,0 +1,116 @@
+//! Per-game install profiles read from `remix-downloader.toml`.
+//!
+//! ```toml
+//! [profiles.portal]
+//! game_exe = 'C:\Games\Portal\hl2.exe'
+//! stream = "dev"
+//! build_type = "release"
+//!
+//! [profiles.portal.dxvk]
+//! "rtx.fpsLimit" = 60
+//! ```
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use serde::Deserialize;
+
+use crate::error::Error;
+use crate::progress::Reporter;
+use crate::{config, Arch, BUILD_TYPES};
+
+pub const DEFAULT_PROFILES_FILE: &str = "remix-downloader.toml";
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ProfilesFile {
+    #[serde(default)]
+    profiles: BTreeMap<String, Profile>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Profile {
+    pub game_exe: Option<PathBuf>,
+    pub game_dir: Option<PathBuf>,
+    /// Detected from `game_exe` when left out
+    pub arch: Option<Arch>,
+    #[serde(default)]
+    pub stream: Stream,
+    #[serde(default = "default_build_type")]
+    pub build_type: String,
+    #[serde(default)]
+    pub move_conflicts: bool,
+    /// Settings written to dxvk.conf after every install
+    #[serde(default)]
+    pub dxvk: BTreeMap<String, toml::Value>,
+    /// Settings written to rtx.conf after every install
+    #[serde(default)]
+    pub rtx: BTreeMap<String, toml::Value>,
+}
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Stream {
+    #[default]
+    Stable,
+    Dev,
+}
+
+fn default_build_type() -> String {
+    BUILD_TYPES[0].to_string()
+}
+
+pub fn load(path: &Path) -> Result<BTreeMap<String, Profile>> {
+    let text = fs::read_to_string(path).map_err(|e| Error::filesystem(path, e))?;
+    let file: ProfilesFile = toml::from_str(&text)
+        .map_err(|e| Error::invalid(format!("Could not parse {}: {}", path.display(), e)))?;
+
+    for (name, profile) in &file.profiles {
+        if !BUILD_TYPES.contains(&profile.build_type.as_str()) {
+            anyhow::bail!(Error::invalid(format!(
+                "Profile {} has unknown build_type \"{}\", expected one of {}",
+                name,
+                profile.build_type,
+                BUILD_TYPES.join(", ")
+            )));
+        }
+        if profile.game_exe.is_none() && profile.game_dir.is_none() {
+            anyhow::bail!(Error::invalid(format!(
+                "Profile {} needs a game_exe or game_dir",
+                name
+            )));
+        }
+    }
+    Ok(file.profiles)
+}
+
+impl Profile {
+    /// Writes the profile's config settings into an install.
+    pub fn apply_overrides(&self, install_path: &Path, reporter: &mut dyn Reporter) -> Result<()> {
+        for (file, settings) in [("dxvk.conf", &self.dxvk), ("rtx.conf", &self.rtx)] {
+            for (key, value) in settings {
+                let value = setting_value(value);
+                config::set(install_path, file, "", key, &value, true, reporter)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Formats a TOML value the way the config files spell it.
+fn setting_value(value: &toml::Value) -> String {
+    match value {
+        toml::Value::String(text) => text.clone(),
+        toml::Value::Boolean(true) => "True".to_string(),
+        toml::Value::Boolean(false) => "False".to_string(),
+        toml::Value::Array(items) => items
+            .iter()
+            .map(setting_value)
+            .collect::<Vec<_>>()
+            .join(", "),
+        other => other.to_string(),
+    }
+}
@@ -0,0 +1,337 @@
+//! Progress of assembling a build: which step it's on, download bytes, extracted files
+//! and the changes made afterwards, along with status messages, warnings, the build
+//! chosen and what was installed. The library reports everything to a `Reporter` and
+//! prints nothing itself; the console ones show progress bars, log lines or JSON events
+//! depending on where the output goes.
+
+use std::io::{self, IsTerminal};
+
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use serde_json::json;
+
+use crate::builds::format_size;
+use crate::install::Installed;
+use crate::manifest::BuildSource;
+use crate::output;
+use crate::plan;
+
+/// A step of assembling a build
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Phase {
+    /// Downloading the runtime package
+    Download,
+    /// Extracting it
+    Extract,
+    /// Removing, renaming and moving files as the layout rules say
+    Layout,
+    /// Putting in the bridge from another package
+    Bridge,
+    /// Adding the dxwrapper D3D8 layer
+    Dx8Wrapper,
+    /// Downloading dxvk.conf and bridge.conf
+    Configs,
+    Licenses,
+    /// Placing the debug symbols next to their binaries
+    Symbols,
+}
+
+impl Phase {
+    /// The name used in JSON events
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Download => "download",
+            Self::Extract => "extract",
+            Self::Layout => "layout",
+            Self::Bridge => "bridge",
+            Self::Dx8Wrapper => "dx8_wrapper",
+            Self::Configs => "configs",
+            Self::Licenses => "licenses",
+            Self::Symbols => "symbols",
+        }
+    }
+
+    pub fn describe(&self) -> &'static str {
+        match self {
+            Self::Download => "Downloading the package",
+            Self::Extract => "Extracting the package",
+            Self::Layout => "Arranging files",
+            Self::Bridge => "Adding the bridge",
+            Self::Dx8Wrapper => "Adding the D3D8 layer",
+            Self::Configs => "Downloading configs",
+            Self::Licenses => "Downloading licenses",
+            Self::Symbols => "Adding debug symbols",
+        }
+    }
+}
+
+/// Receives progress and messages while the library works. Everything defaults to doing
+/// nothing, so implementations only handle what they show.
+pub trait Reporter {
+    /// A status message for people, which may carry terminal colors
+    fn message(&mut self, _message: &str) {}
+    /// Something went wrong that doesn't stop the work
+    fn warning(&mut self, _message: &str) {}
+    /// Assembling the build takes `steps` phases
+    fn start(&mut self, _steps: usize) {}
+    /// Phase number `step` began, counting from 1
+    fn phase(&mut self, _phase: Phase, _step: usize) {}
+    /// A download began; `total` is 0 when the server didn't send the size
+    fn download_started(&mut self, _url: &str, _total: u64) {}
+    /// `downloaded` bytes of `url` have arrived so far
+    fn download_progress(&mut self, _url: &str, _downloaded: u64, _total: u64) {}
+    fn download_finished(&mut self, _url: &str, _size: u64) {}
+    /// `done` of the `total` entries in `archive` are extracted
+    fn extracted(&mut self, _archive: &str, _done: usize, _total: usize) {}
+    /// A file was removed, renamed, moved or added after extraction
+    fn changed(&mut self, _change: &str) {}
+    /// The build is assembled
+    fn finish(&mut self) {}
+    /// The build to install was picked, before its notes are shown
+    fn resolved(&mut self, _build: &BuildSource) {}
+    /// A dry run finished with this plan, once it was shown
+    fn planned(&mut self, _plan: &plan::Summary) {}
+    /// The install finished
+    fn installed(&mut self, _installed: &Installed) {}
+}
+
+/// Reports nothing, for callers that don't show progress
+pub struct Silent;
+
+impl Reporter for Silent {}
+
+/// The reporter for the console: JSON events with `--output json`, progress bars on a
+/// terminal and plain log lines otherwise.
+pub fn console() -> Box<dyn Reporter> {
+    if output::is_json() {
+        Box::new(Json::default())
+    } else if io::stderr().is_terminal() {
+        Box::new(Bars::new())
+    } else {
+        Box::new(Log::default())
+    }
+}
+
+/// An overall bar for the steps with one below it for the current download or extraction
+pub struct Bars {
+    multi: MultiProgress,
+    overall: ProgressBar,
+    current: Option<ProgressBar>,
+    /// The phase the overall bar shows
+    phase: &'static str,
+}
+
+impl Bars {
+    pub fn new() -> Self {
+        Self {
+            multi: MultiProgress::new(),
+            overall: ProgressBar::hidden(),
+            current: None,
+            phase: "",
+        }
+    }
+
+    fn replace_current(&mut self, length: u64, template: &str) -> &ProgressBar {
+        if let Some(current) = self.current.take() {
+            current.finish_and_clear();
+        }
+        let bar = self.multi.add(ProgressBar::new(length));
+        bar.set_style(
+            ProgressStyle::default_bar()
+                .template(template)
+                .unwrap()
+                .progress_chars("#>-"),
+        );
+        self.current.insert(bar)
+    }
+}
+
+impl Default for Bars {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Reporter for Bars {
+    fn message(&mut self, message: &str) {
+        // Printed above the bars instead of through them
+        self.multi.suspend(|| status!("{}", message));
+    }
+
+    fn warning(&mut self, message: &str) {
+        self.multi.suspend(|| output::warn(message));
+    }
+
+    fn start(&mut self, steps: usize) {
+        self.overall = self.multi.add(ProgressBar::new(steps as u64));
+        self.overall.set_style(
+            ProgressStyle::default_bar()
+                .template("{spinner:.green} [{elapsed_precise}] step {pos}/{len} {msg}")
+                .unwrap(),
+        );
+    }
+
+    fn phase(&mut self, phase: Phase, step: usize) {
+        self.overall.set_position(step as u64);
+        self.phase = phase.describe();
+        self.overall.set_message(self.phase);
+    }
+
+    fn download_started(&mut self, _url: &str, total: u64) {
+        self.replace_current(
+            total,
+            "  [{bar:40.cyan/blue}] {bytes}/{total_bytes} ({eta})",
+        );
+    }
+
+    fn download_progress(&mut self, _url: &str, downloaded: u64, _total: u64) {
+        if let Some(current) = &self.current {
+            current.set_position(downloaded);
+        }
+    }
+
+    fn download_finished(&mut self, _url: &str, _size: u64) {
+        if let Some(current) = self.current.take() {
+            current.finish_and_clear();
+        }
+    }
+
+    fn extracted(&mut self, _archive: &str, done: usize, total: usize) {
+        if done == 1 || self.current.is_none() {
+            self.replace_current(total as u64, "  [{bar:40.cyan/blue}] {pos}/{len} files");
+        }
+        if let Some(current) = &self.current {
+            current.set_position(done as u64);
+        }
+        if done == total {
+            if let Some(current) = self.current.take() {
+                current.finish_and_clear();
+            }
+        }
+    }
+
+    fn changed(&mut self, change: &str) {
+        self.overall
+            .set_message(format!("{}: {}", self.phase, change));
+    }
+
+    fn finish(&mut self) {
+        if let Some(current) = self.current.take() {
+            current.finish_and_clear();
+        }
+        self.overall.finish_and_clear();
+    }
+}
+
+/// One line per step, download and archive, for logs and other non-terminal output
+#[derive(Default)]
+pub struct Log {
+    steps: usize,
+}
+
+impl Reporter for Log {
+    fn message(&mut self, message: &str) {
+        status!("{}", message);
+    }
+
+    fn warning(&mut self, message: &str) {
+        output::warn(message);
+    }
+
+    fn start(&mut self, steps: usize) {
+        self.steps = steps;
+    }
+
+    fn phase(&mut self, phase: Phase, step: usize) {
+        status!("[{}/{}] {}", step, self.steps, phase.describe());
+    }
+
+    fn download_finished(&mut self, url: &str, size: u64) {
+        status!("Downloaded {} ({})", url, format_size(size));
+    }
+
+    fn extracted(&mut self, archive: &str, done: usize, total: usize) {
+        if done == total {
+            status!("Extracted {} entries from {}", total, archive);
+        }
+    }
+
+    fn changed(&mut self, change: &str) {
+        status!("  {}", change);
+    }
+}
+
+/// Events on stdout for launchers, as described for `--output json`
+#[derive(Default)]
+pub struct Json {
+    steps: usize,
+    /// Bytes of the current download at the last progress event
+    reported: u64,
+}
+
+/// Progress events are sent once per this many bytes
+const PROGRESS_STEP: u64 = 1024 * 1024;
+
+impl Reporter for Json {
+    fn message(&mut self, message: &str) {
+        status!("{}", message);
+    }
+
+    fn warning(&mut self, message: &str) {
+        output::warn(message);
+    }
+
+    fn start(&mut self, steps: usize) {
+        self.steps = steps;
+    }
+
+    fn phase(&mut self, phase: Phase, step: usize) {
+        output::event(
+            "phase",
+            json!({ "phase": phase.name(), "step": step, "steps": self.steps }),
+        );
+    }
+
+    fn download_started(&mut self, _url: &str, _total: u64) {
+        self.reported = 0;
+    }
+
+    fn download_progress(&mut self, url: &str, downloaded: u64, total: u64) {
+        // One event per MiB is plenty for a launcher's progress bar
+        if self.reported / PROGRESS_STEP != downloaded / PROGRESS_STEP {
+            output::event(
+                "progress",
+                json!({ "url": url, "downloaded": downloaded, "total": total }),
+            );
+            self.reported = downloaded;
+        }
+    }
+
+    fn download_finished(&mut self, url: &str, size: u64) {
+        output::event("downloaded", json!({ "url": url, "size": size }));
+    }
+
+    fn extracted(&mut self, archive: &str, done: usize, total: usize) {
+        if done == total {
+            output::event("extracted", json!({ "archive": archive, "entries": total }));
+        }
+    }
+
+    fn changed(&mut self, change: &str) {
+        output::event("changed", json!({ "change": change }));
+    }
+
+    fn resolved(&mut self, build: &BuildSource) {
+        output::event("resolved", json!({ "build": build }));
+    }
+
+    fn planned(&mut self, plan: &plan::Summary) {
+        output::event("plan", json!(plan));
+    }
+
+    fn installed(&mut self, installed: &Installed) {
+        output::event(
+            "installed",
+            json!({ "path": installed.path, "files": installed.files }),
+        );
+    }
+}
@@ -0,0 +1,192 @@
+//! A local stand-in for the GitHub API, nightly.link and the raw file host, so the
+//! downloader can run end to end without the network.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, BufRead, BufReader, Cursor, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use serde_json::Value;
+use zip::write::FileOptions;
+use zip::ZipWriter;
+
+type Routes = Arc<Mutex<HashMap<String, Vec<u8>>>>;
+
+/// An HTTP server on a free local port answering GET and HEAD requests from a table of
+/// paths, query strings included, and single byte ranges. Anything not in the table is
+/// a 404.
+pub struct MockServer {
+    /// `http://127.0.0.1:<port>`, without a trailing slash
+    pub base: String,
+    routes: Routes,
+    requests: Arc<Mutex<Vec<String>>>,
+}
+
+impl MockServer {
+    pub fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind the mock server");
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        let routes = Routes::default();
+        let requests = Arc::new(Mutex::new(Vec::new()));
+
+        let (served_routes, served_requests) = (routes.clone(), requests.clone());
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let (routes, requests) = (served_routes.clone(), served_requests.clone());
+                thread::spawn(move || {
+                    let _ = serve(stream, &routes, &requests);
+                });
+            }
+        });
+
+        Self {
+            base,
+            routes,
+            requests,
+        }
+    }
+
+    /// Serves `body` at `path`, which starts with a slash.
+    pub fn route(&self, path: &str, body: impl Into<Vec<u8>>) {
+        self.routes
+            .lock()
+            .unwrap()
+            .insert(path.to_string(), body.into());
+    }
+
+    pub fn route_json(&self, path: &str, value: Value) {
+        self.route(path, value.to_string());
+    }
+
+    /// The URL `path` is served at.
+    pub fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base, path)
+    }
+
+    /// Requests received so far, as `METHOD /path?query`, followed by `bytes=start-end`
+    /// for range requests.
+    pub fn requests(&self) -> Vec<String> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+fn serve(stream: TcpStream, routes: &Routes, requests: &Mutex<Vec<String>>) -> io::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // Keep only the range of the headers; the downloader never sends a body
+    let mut range = None;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 || line == "\r\n" {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.eq_ignore_ascii_case("range") {
+                range = value.trim().strip_prefix("bytes=").and_then(|bytes| {
+                    let (start, end) = bytes.split_once('-')?;
+                    Some((start.parse::<usize>().ok()?, end.parse::<usize>().ok()?))
+                });
+            }
+        }
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or_default();
+    let path = parts.next().unwrap_or_default();
+    requests.lock().unwrap().push(match range {
+        Some((start, end)) => format!("{} {} bytes={}-{}", method, path, start, end),
+        None => format!("{} {}", method, path),
+    });
+
+    let (status, body, content_range) = match (routes.lock().unwrap().get(path), range) {
+        (Some(body), Some((start, end))) if start <= end && end < body.len() => (
+            "206 Partial Content",
+            body[start..=end].to_vec(),
+            format!("Content-Range: bytes {}-{}/{}\r\n", start, end, body.len()),
+        ),
+        (Some(body), _) => ("200 OK", body.clone(), String::new()),
+        (None, _) => ("404 Not Found", b"Not Found".to_vec(), String::new()),
+    };
+    let mut stream = stream;
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Length: {}\r\n{}Connection: close\r\n\r\n",
+        status,
+        body.len(),
+        content_range
+    )?;
+    if method != "HEAD" {
+        stream.write_all(&body)?;
+    }
+    stream.flush()
+}
+
+/// A zip holding each `(path, contents)` pair, paths using forward slashes.
+pub fn zip(entries: &[(&str, &str)]) -> Vec<u8> {
+    let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+    for (path, contents) in entries {
+        writer.start_file(*path, FileOptions::default()).unwrap();
+        writer.write_all(contents.as_bytes()).unwrap();
+    }
+    writer.finish().unwrap().into_inner()
+}
+
+/// The smallest image the downloader reads as a 32-bit Windows DLL: DOS and COFF headers
+/// with no sections.
+pub fn dll() -> Vec<u8> {
+    let mut image = vec![0; 0x40 + 24];
+    image[..2].copy_from_slice(b"MZ");
+    image[0x3c..0x40].copy_from_slice(&0x40u32.to_le_bytes());
+    image[0x40..0x44].copy_from_slice(b"PE\0\0");
+    image[0x44..0x46].copy_from_slice(&0x014cu16.to_le_bytes());
+    image
+}
+
+/// An empty folder for one test, named after it.
+pub fn work_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!(
+        "rtx-remix-downloader-{}-{}",
+        name,
+        std::process::id()
+    ));
+    if dir.exists() {
+        fs::remove_dir_all(&dir).unwrap();
+    }
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Runs the downloader binary in `dir`.
+pub fn run(dir: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_rtx_remix_downloader"))
+        .args(args)
+        .current_dir(dir)
+        // Keep a proxy configured on the machine away from the mock server
+        .env("NO_PROXY", "127.0.0.1")
+        .output()
+        .expect("run the downloader")
+}
+
+/// Every file below `root`, as sorted forward-slash relative paths.
+pub fn list_files(root: &Path) -> Vec<String> {
+    fn walk(root: &Path, dir: &Path, files: &mut Vec<String>) {
+        for entry in fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.is_dir() {
+                walk(root, &path, files);
+            } else {
+                let relative = path.strip_prefix(root).unwrap();
+                files.push(relative.to_string_lossy().replace('\\', "/"));
+            }
+        }
+    }
+    let mut files = Vec::new();
+    walk(root, root, &mut files);
+    files.sort();
+    files
+}
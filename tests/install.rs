@@ -0,0 +1,526 @@
+//! Stable and dev installs for both architectures, run with the real binary against a
+//! mock GitHub serving small synthetic packages.
+
+mod common;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde_json::json;
+use sha2::{Digest, Sha256};
+
+use common::{dll, list_files, run, work_dir, zip, MockServer};
+
+const RELEASE_TAG: &str = "remix-1.2.3";
+const DXVK_SHA: &str = "d0d0d0d0d0d0d0d0d0d0d0d0d0d0d0d0d0d0d0d0";
+const BRIDGE_SHA: &str = "b1b1b1b1b1b1b1b1b1b1b1b1b1b1b1b1b1b1b1b1";
+const RUN_CREATED_AT: &str = "2026-01-02T03:04:05Z";
+
+/// Moves the hosts to the mock server and leaves the rest to the built-in catalog.
+const CATALOG: &str = r#"
+[hosts]
+api = "{base}/api"
+raw = "{base}/raw"
+nightly_link = "{base}/nightly"
+"#;
+
+/// A stable release package, laid out like the published ones with the runtime in .trex
+fn release_package() -> Vec<u8> {
+    zip(&[
+        ("d3d9.dll", "bridge client"),
+        ("d3d8to9.dll", "d3d8to9"),
+        ("ThirdPartyLicenses-d3d8to9.txt", "d3d8to9 license"),
+        (".trex/d3d9.dll", "runtime"),
+        (".trex/dxvk.dll", "dxvk"),
+        (".trex/NvRemixBridge.exe", "bridge server"),
+        (".trex/d3d9.pdb", "runtime symbols"),
+    ])
+}
+
+/// Serves a stable release, a dev workflow run and the files installs download besides.
+fn mock_github() -> MockServer {
+    let server = MockServer::start();
+
+    // The stable release
+    let release_zip = format!("/download/{}/rtx-remix-1.2.3-release.zip", RELEASE_TAG);
+    server.route_json(
+        "/api/repos/NVIDIAGameWorks/rtx-remix/releases/latest",
+        json!({
+            "tag_name": RELEASE_TAG,
+            "assets": [
+                {
+                    "name": "rtx-remix-1.2.3-release-symbols.zip",
+                    "browser_download_url": server.url("/download/symbols.zip"),
+                },
+                {
+                    "name": "rtx-remix-1.2.3-release.zip",
+                    "browser_download_url": server.url(&release_zip),
+                },
+            ],
+        }),
+    );
+    server.route(&release_zip, release_package());
+
+    // Dev builds: the latest successful run on main has both packages as artifacts
+    server.route_json(
+        "/api/repos/NVIDIAGameWorks/dxvk-remix/actions/runs?branch=main",
+        json!({
+            "workflow_runs": [
+                {
+                    "id": 1001,
+                    "run_number": 41,
+                    "conclusion": "success",
+                    "head_sha": DXVK_SHA,
+                    "created_at": RUN_CREATED_AT,
+                    "artifacts_url": server.url("/api/repos/NVIDIAGameWorks/dxvk-remix/actions/runs/1001/artifacts"),
+                },
+                {
+                    "id": 1002,
+                    "run_number": 42,
+                    "conclusion": "failure",
+                    "head_sha": "f00d",
+                    "created_at": RUN_CREATED_AT,
+                    "artifacts_url": server.url("/api/repos/NVIDIAGameWorks/dxvk-remix/actions/runs/1002/artifacts"),
+                },
+            ],
+        }),
+    );
+    server.route_json(
+        "/api/repos/NVIDIAGameWorks/dxvk-remix/actions/runs/1001/artifacts",
+        json!({
+            "artifacts": [
+                { "id": 11, "name": "rtx-remix-for-x86-games-d0d0d0d-release" },
+                { "id": 12, "name": "rtx-remix-for-x86-games-d0d0d0d-release-symbols" },
+                { "id": 13, "name": "rtx-remix-d0d0d0d-debug" },
+                { "id": 14, "name": "rtx-remix-d0d0d0d-release" },
+            ],
+        }),
+    );
+    server.route_json(
+        &format!(
+            "/api/repos/NVIDIAGameWorks/bridge-remix/commits?sha=main&until={}&per_page=1",
+            RUN_CREATED_AT
+        ),
+        json!([{ "sha": BRIDGE_SHA }]),
+    );
+    server.route(
+        "/nightly/NVIDIAGameWorks/dxvk-remix/actions/artifacts/11.zip",
+        zip(&[
+            ("d3d9.dll", "bridge client"),
+            ("NvRemixLauncher32.exe", "launcher"),
+            ("artifacts_readme.txt", "readme"),
+            (".trex/d3d9.dll", "runtime"),
+            (".trex/NvRemixBridge.exe", "bridge server"),
+            (".trex/d3d9.pdb", "runtime symbols"),
+        ]),
+    );
+    server.route(
+        "/nightly/NVIDIAGameWorks/dxvk-remix/actions/artifacts/14.zip",
+        zip(&[
+            ("d3d9.dll", "runtime"),
+            ("d3d9.pdb", "runtime symbols"),
+            ("CRC.txt", "checksums"),
+        ]),
+    );
+
+    // Configs and licenses at the tag or commit each build came from, and on main for
+    // local packages, which don't say
+    for git_ref in [RELEASE_TAG, DXVK_SHA, "main"] {
+        let dxvk = format!("/raw/NVIDIAGameWorks/dxvk-remix/{}", git_ref);
+        server.route(&format!("{}/dxvk.conf", dxvk), "# dxvk.conf\n");
+        server.route(
+            &format!("{}/ThirdPartyLicenses.txt", dxvk),
+            "dxvk-remix third party licenses",
+        );
+    }
+    for git_ref in [RELEASE_TAG, BRIDGE_SHA, "main"] {
+        let bridge = format!("/raw/NVIDIAGameWorks/bridge-remix/{}", git_ref);
+        server.route(&format!("{}/bridge.conf", bridge), "# bridge.conf\n");
+        server.route(
+            &format!("{}/ThirdPartyLicenses.txt", bridge),
+            "bridge-remix third party licenses",
+        );
+    }
+    for git_ref in [RELEASE_TAG, "main"] {
+        server.route(
+            &format!("/raw/NVIDIAGameWorks/rtx-remix/{}/LICENSE.txt", git_ref),
+            "RTX Remix license",
+        );
+    }
+
+    // dxwrapper's D3D8 layer for x86 games
+    server.route(
+        "/nightly/elishacloud/dxwrapper/workflows/ci/master/dx8%20game%20binaries.zip",
+        zip(&[
+            ("d3d8.dll", "dxwrapper d3d8"),
+            ("d3d8to9.dll", "d3d8to9"),
+            ("dxwrapper.dll", "dxwrapper"),
+            ("dxwrapper.ini", "[Compatibility]\n"),
+        ]),
+    );
+    server.route(
+        "/raw/elishacloud/dxwrapper/refs/heads/master/License.txt",
+        "dxwrapper license",
+    );
+
+    server
+}
+
+/// Installs with `args` in a fresh folder and returns the remix folder it made.
+fn install(server: &MockServer, name: &str, args: &[&str]) -> PathBuf {
+    install_in(server, &work_dir(name), args)
+}
+
+/// Installs with `args`, running from `dir`, and returns the remix folder.
+fn install_in(server: &MockServer, dir: &Path, args: &[&str]) -> PathBuf {
+    let catalog = dir.join("catalog.toml");
+    fs::write(&catalog, CATALOG.replace("{base}", &server.base)).unwrap();
+
+    let mut command = vec!["--catalog", catalog.to_str().unwrap(), "install"];
+    command.extend_from_slice(args);
+    let output = run(dir, &command);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        output.status.success(),
+        "install {:?} failed\nstdout:\n{}\nstderr:\n{}",
+        args,
+        String::from_utf8_lossy(&output.stdout),
+        stderr
+    );
+    // Every file was found at the build's own tag or commit
+    assert!(
+        !stderr.contains("using the version from main"),
+        "fell back to main:\n{}",
+        stderr
+    );
+    dir.join("remix")
+}
+
+fn read(remix: &Path, path: &str) -> String {
+    fs::read_to_string(remix.join(path)).unwrap()
+}
+
+/// Checks the install passes `verify` and removes its folder.
+fn verify_and_clean_up(remix: &Path) {
+    let dir = remix.parent().unwrap();
+    let output = run(dir, &["verify"]);
+    assert!(
+        output.status.success(),
+        "verify failed:\n{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn stable_x86() {
+    let server = mock_github();
+    let remix = install(&server, "stable-x86", &["--arch", "x86"]);
+
+    assert_eq!(
+        list_files(&remix),
+        [
+            ".remix-defaults/.trex/bridge.conf",
+            ".remix-defaults/dxvk.conf",
+            ".trex/NvRemixBridge.exe",
+            ".trex/bridge.conf",
+            ".trex/d3d9.dll",
+            ".trex/dxvk.dll",
+            "LICENSE.txt",
+            "ThirdPartyLicenses-bridge.txt",
+            "ThirdPartyLicenses-dxvk.txt",
+            "ThirdPartyLicenses-dxwrapper.txt",
+            "build-names.txt",
+            "d3d8_off.dll",
+            "d3d9.dll",
+            "dxvk.conf",
+            "dxwrapper.dll",
+            "dxwrapper.ini",
+            "remix-manifest.json",
+        ]
+    );
+    assert_eq!(read(&remix, "d3d8_off.dll"), "dxwrapper d3d8");
+    assert_eq!(read(&remix, "d3d9.dll"), "bridge client");
+    assert_eq!(read(&remix, ".trex/d3d9.dll"), "runtime");
+    assert!(read(&remix, "build-names.txt").contains("rtx-remix-1.2.3-release.zip"));
+    verify_and_clean_up(&remix);
+}
+
+#[test]
+fn stable_x64() {
+    let server = mock_github();
+    let remix = install(&server, "stable-x64", &["--arch", "x64"]);
+
+    // Flattened out of .trex, with the runtime replacing the bridge client
+    assert_eq!(
+        list_files(&remix),
+        [
+            "LICENSE.txt",
+            "ThirdPartyLicenses-d3d8to9.txt",
+            "ThirdPartyLicenses.txt",
+            "build-names.txt",
+            "d3d9.dll",
+            "dxvk.dll",
+            "remix-manifest.json",
+        ]
+    );
+    assert_eq!(read(&remix, "d3d9.dll"), "runtime");
+    assert!(!server
+        .requests()
+        .iter()
+        .any(|request| request.contains("/elishacloud/dxwrapper/")));
+    verify_and_clean_up(&remix);
+}
+
+#[test]
+fn dev_x86() {
+    let server = mock_github();
+    let remix = install(&server, "dev-x86", &["--dev", "--arch", "x86"]);
+
+    assert_eq!(
+        list_files(&remix),
+        [
+            ".remix-defaults/.trex/bridge.conf",
+            ".remix-defaults/dxvk.conf",
+            ".trex/NvRemixBridge.exe",
+            ".trex/bridge.conf",
+            ".trex/d3d9.dll",
+            "LICENSE.txt",
+            "NvRemixLauncher32.exe",
+            "ThirdPartyLicenses-bridge.txt",
+            "ThirdPartyLicenses-dxvk.txt",
+            "ThirdPartyLicenses-dxwrapper.txt",
+            "build-names.txt",
+            "d3d8_off.dll",
+            "d3d9.dll",
+            "dxvk.conf",
+            "dxwrapper.dll",
+            "dxwrapper.ini",
+            "remix-manifest.json",
+        ]
+    );
+    assert_eq!(read(&remix, "d3d8_off.dll"), "dxwrapper d3d8");
+    assert!(read(&remix, "build-names.txt").contains("rtx-remix-for-x86-games-d0d0d0d-release"));
+    // The package comes from the latest successful run, not the failed one after it
+    let manifest = read(&remix, "remix-manifest.json");
+    assert!(manifest.contains(DXVK_SHA), "{}", manifest);
+    assert!(manifest.contains(BRIDGE_SHA), "{}", manifest);
+    verify_and_clean_up(&remix);
+}
+
+#[test]
+fn dev_x64() {
+    let server = mock_github();
+    let remix = install(&server, "dev-x64", &["--dev", "--arch", "x64"]);
+
+    assert_eq!(
+        list_files(&remix),
+        [
+            "LICENSE.txt",
+            "ThirdPartyLicenses.txt",
+            "build-names.txt",
+            "d3d9.dll",
+            "remix-manifest.json",
+        ]
+    );
+    assert!(read(&remix, "build-names.txt").contains("rtx-remix-d0d0d0d-release"));
+    verify_and_clean_up(&remix);
+}
+
+#[test]
+fn switching_a_game_to_x64_keeps_edited_configs() {
+    let server = mock_github();
+    let dir = work_dir("switch-to-x64");
+    let game_dir = dir.join("game");
+    fs::create_dir(&game_dir).unwrap();
+    let game_dir_arg = game_dir.to_str().unwrap();
+
+    install_in(
+        &server,
+        &dir,
+        &["--arch", "x86", "--game-dir", game_dir_arg],
+    );
+    fs::write(game_dir.join("dxvk.conf"), "rtx.fpsLimit = 60\n").unwrap();
+    install_in(
+        &server,
+        &dir,
+        &["--arch", "x64", "--game-dir", game_dir_arg],
+    );
+
+    // The bridge and D3D8 layer are gone, but the configs x64 builds don't ship stay
+    assert!(!game_dir.join(".trex/NvRemixBridge.exe").exists());
+    assert!(!game_dir.join("d3d8_off.dll").exists());
+    assert_eq!(read(&game_dir, "dxvk.conf"), "rtx.fpsLimit = 60\n");
+    assert!(game_dir.join(".trex/bridge.conf").exists());
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn conflicts_are_only_moved_by_an_install_that_succeeds() {
+    let server = mock_github();
+    let dir = work_dir("move-conflicts");
+    let game_dir = dir.join("game");
+    fs::create_dir(&game_dir).unwrap();
+    fs::write(game_dir.join("dxgi.dll"), dll()).unwrap();
+    fs::write(game_dir.join("ReShade.ini"), "").unwrap();
+    let catalog = dir.join("catalog.toml");
+    fs::write(&catalog, CATALOG.replace("{base}", &server.base)).unwrap();
+    let args = [
+        "--catalog",
+        catalog.to_str().unwrap(),
+        "install",
+        "--arch",
+        "x64",
+        "--move-conflicts",
+        "--game-dir",
+        game_dir.to_str().unwrap(),
+    ];
+
+    // A package that can't be extracted stops the install before the game folder is touched
+    let release_zip = format!("/download/{}/rtx-remix-1.2.3-release.zip", RELEASE_TAG);
+    server.route(&release_zip, "not a zip");
+    assert!(!run(&dir, &args).status.success());
+    assert!(game_dir.join("dxgi.dll").exists());
+    assert!(!game_dir.join(".remix-backup").exists());
+
+    server.route(&release_zip, release_package());
+    install_in(&server, &dir, &args[3..]);
+    assert!(!game_dir.join("dxgi.dll").exists());
+    assert!(game_dir.join(".remix-backup/files/dxgi.dll").exists());
+    assert_eq!(read(&game_dir, "d3d9.dll"), "runtime");
+
+    let output = run(&dir, &["uninstall", game_dir.to_str().unwrap()]);
+    assert!(
+        output.status.success(),
+        "uninstall failed:\n{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(fs::read(game_dir.join("dxgi.dll")).unwrap(), dll());
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn conflicts_are_identified_by_catalog_hashes() {
+    let server = mock_github();
+    let dir = work_dir("conflict-hashes");
+    let game_dir = dir.join("game");
+    fs::create_dir(&game_dir).unwrap();
+    // Nothing in the DLL or next to it names the wrapper
+    fs::write(game_dir.join("dxgi.dll"), dll()).unwrap();
+    let catalog = dir.join("catalog.toml");
+    let sha256 = format!("{:x}", Sha256::digest(dll()));
+    fs::write(
+        &catalog,
+        format!(
+            "{}\n[[wrapper_hashes]]\nsha256 = \"{}\"\nwrapper = \"ReShade\"\n",
+            CATALOG.replace("{base}", &server.base),
+            sha256
+        ),
+    )
+    .unwrap();
+
+    let output = run(
+        &dir,
+        &[
+            "--catalog",
+            catalog.to_str().unwrap(),
+            "install",
+            "--arch",
+            "x64",
+            "--move-conflicts",
+            "--game-dir",
+            game_dir.to_str().unwrap(),
+        ],
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "install failed:\n{}", stdout);
+    assert!(stdout.contains("ReShade (known file hash)"), "{}", stdout);
+    assert!(game_dir.join(".remix-backup/files/dxgi.dll").exists());
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn local_stable_zip_x64() {
+    let server = mock_github();
+    let dir = work_dir("local-stable-x64");
+    fs::write(dir.join("release.zip"), release_package()).unwrap();
+    let remix = install_in(
+        &server,
+        &dir,
+        &["--runtime", "release.zip", "--arch", "x64"],
+    );
+
+    // Recognised as a release, so it's flattened like a downloaded one
+    assert!(!remix.join(".trex").exists());
+    assert_eq!(read(&remix, "d3d9.dll"), "runtime");
+    // The zip is recorded by its full path, for repairs run from elsewhere
+    let manifest: serde_json::Value =
+        serde_json::from_str(&read(&remix, "remix-manifest.json")).unwrap();
+    let local_zip = manifest["source"]["local_zip"].as_str().unwrap();
+    assert!(Path::new(local_zip).is_absolute(), "{}", local_zip);
+    verify_and_clean_up(&remix);
+}
+
+#[test]
+fn dry_run_reads_zip_listings_remotely() {
+    let server = mock_github();
+    let dir = work_dir("dry-run-x86");
+    let catalog = dir.join("catalog.toml");
+    fs::write(&catalog, CATALOG.replace("{base}", &server.base)).unwrap();
+    let output = run(
+        &dir,
+        &[
+            "--catalog",
+            catalog.to_str().unwrap(),
+            "--output",
+            "json",
+            "install",
+            "--dry-run",
+            "--arch",
+            "x86",
+        ],
+    );
+    assert!(
+        output.status.success(),
+        "dry run failed:\n{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(!dir.join("remix").exists());
+
+    // The package was sized with a HEAD and listed through range requests, never fetched whole
+    let release_zip = format!("/download/{}/rtx-remix-1.2.3-release.zip", RELEASE_TAG);
+    let requests = server.requests();
+    assert!(requests.contains(&format!("HEAD {}", release_zip)));
+    assert!(requests
+        .iter()
+        .any(|request| request.starts_with(&format!("GET {} bytes=", release_zip))));
+    assert!(!requests.contains(&format!("GET {}", release_zip)));
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let plan: serde_json::Value = stdout
+        .lines()
+        .filter_map(|line| serde_json::from_str::<serde_json::Value>(line).ok())
+        .find(|event| event["event"] == "plan")
+        .expect("a plan event");
+    let release = plan["downloads"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|download| download["url"] == server.url(&release_zip))
+        .expect("the release package in the downloads");
+    assert_eq!(release["size"], release_package().len());
+    assert!(plan["extracted"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .any(|archive| archive["files"]
+            .as_array()
+            .unwrap()
+            .contains(&json!(".trex/d3d9.dll"))));
+    assert_eq!(plan["target_exists"], false);
+    assert!(plan["effects"]
+        .as_array()
+        .unwrap()
+        .contains(&json!({ "action": "add", "path": "d3d8_off.dll" })));
+    fs::remove_dir_all(&dir).unwrap();
+}